@@ -6,7 +6,7 @@ easier to scale to games like Rock, Paper, Scissors, Lizard, Spock.
 use std::fs;
 use std::convert::TryFrom;
 
-fn read_lines(filename: &str) -> Vec<(i32, i32)> {
+pub fn read_lines(filename: &str) -> Vec<(i32, i32)> {
     /*
     Open a text file and return a Vector of Strings representing the individual
     lines.
@@ -22,7 +22,7 @@ fn read_lines(filename: &str) -> Vec<(i32, i32)> {
     lines
 }
 
-fn process_lines(lines: &Vec<(i32, i32)>) -> i32 {
+pub fn process_lines(lines: &[(i32, i32)]) -> i32 {
     /*
      Given a list of structs representing the move your opponent will play and
      the move you should play for each round, calculate what your score should
@@ -0,0 +1,242 @@
+/*
+Plays the opponent's column of the Day 2 strategy guide against several
+different strategies, instead of just scoring the guide as written. Every
+strategy produces a list of (opponent move, response move) pairs, which are
+then scored by day02_alt_solution::process_lines(), so the scoring rules only
+live in one place.
+*/
+use std::fmt;
+use std::collections::BTreeMap;
+use crate::day02_alt_solution::{read_lines, process_lines};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    FollowGuide,
+    AlwaysWin,
+    Random,
+    FrequencyCounter,
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self {
+            Strategy::FollowGuide      => "follow the guide",
+            Strategy::AlwaysWin        => "always win",
+            Strategy::Random           => "random",
+            Strategy::FrequencyCounter => "frequency counter",
+        };
+        write!(f, "{}", result)
+    }
+}
+
+pub const ALL_STRATEGIES: [Strategy; 4] = [
+    Strategy::FollowGuide,
+    Strategy::AlwaysWin,
+    Strategy::Random,
+    Strategy::FrequencyCounter,
+];
+
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        /*
+        A small xorshift64* generator, so that every simulated run can be
+        reproduced from its seed without pulling in another crate. The seed is
+        mixed first because xorshift gets stuck on a state of zero.
+        */
+        let mut state: u64 = seed ^ 0x9E37_79B9_7F4A_7C15;
+        if state == 0 {
+            state = 0x2545_F491_4F6C_DD1D;
+        }
+        Rng { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next_below(&mut self, limit: u64) -> u64 {
+        self.next_u64() % limit
+    }
+}
+
+fn winning_move(opponent: i32) -> i32 {
+    // each move loses to the one after it, see day02_alt_solution.rs
+    (opponent + 1) % 3
+}
+
+fn predict_move(seen: &[usize; 3], rng: &mut Rng) -> i32 {
+    /*
+    Predict the opponent's next move as the one they've played most often so
+    far. Ties (including the very first round, before anything has been seen)
+    are broken randomly.
+    */
+    let most_seen: usize = *seen.iter().max().unwrap();
+    let candidates: Vec<i32> = (0..3).filter(|each| seen[*each as usize] == most_seen).collect();
+    candidates[rng.next_below(candidates.len() as u64) as usize]
+}
+
+pub fn choose_moves(lines: &[(i32, i32)], strategy: Strategy, rng: &mut Rng) -> Vec<(i32, i32)> {
+    /*
+     Given the strategy guide, return the (opponent move, response move) pairs
+     that the given strategy would play. Only FollowGuide looks at the second
+     column of the guide; the other strategies only ever see the opponent's
+     moves, and FrequencyCounter only sees the moves from earlier rounds.
+     */
+    let mut rounds: Vec<(i32, i32)> = Vec::new();
+    let mut seen: [usize; 3] = [0; 3];
+    for (opponent, guide) in lines {
+        let response: i32 = match strategy {
+            Strategy::FollowGuide      => *guide,
+            Strategy::AlwaysWin        => winning_move(*opponent),
+            Strategy::Random           => rng.next_below(3) as i32,
+            Strategy::FrequencyCounter => winning_move(predict_move(&seen, rng)),
+        };
+        seen[*opponent as usize] += 1;
+        rounds.push((*opponent, response));
+    }
+    rounds
+}
+
+pub fn play(lines: &[(i32, i32)], strategy: Strategy, seed: u64) -> i32 {
+    /*
+     Play a single game with the given strategy and return its score.
+     */
+    let mut rng: Rng = Rng::new(seed);
+    process_lines(&choose_moves(lines, strategy, &mut rng))
+}
+
+pub fn maximum_score(lines: &[(i32, i32)]) -> i32 {
+    /*
+     Return the highest score that can be achieved against the opponent's
+     moves, by trying every response in every round and keeping the best.
+     */
+    let mut best_rounds: Vec<(i32, i32)> = Vec::new();
+    for (opponent, _guide) in lines {
+        let response: i32 = (0..3).max_by_key(|each| process_lines(&[(*opponent, *each)])).unwrap();
+        best_rounds.push((*opponent, response));
+    }
+    process_lines(&best_rounds)
+}
+
+#[derive(Debug)]
+pub struct ScoreDistribution {
+    pub strategy: Strategy,
+    pub runs: usize,
+    pub min: i32,
+    pub max: i32,
+    pub mean: f64,
+    pub histogram: BTreeMap<i32, usize>,
+}
+
+impl fmt::Display for ScoreDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<18} runs: {:>5}  min: {:>6}  max: {:>6}  mean: {:>9.2}  distinct scores: {}",
+            self.strategy.to_string(), self.runs, self.min, self.max, self.mean, self.histogram.len())
+    }
+}
+
+pub fn simulate(lines: &[(i32, i32)], strategy: Strategy, runs: usize, seed: u64) -> ScoreDistribution {
+    /*
+     Play the given strategy `runs` times, seeding run n with seed + n, and
+     collect the distribution of the scores.
+     */
+    let mut histogram: BTreeMap<i32, usize> = BTreeMap::new();
+    let mut total: i64 = 0;
+    for run in 0..runs {
+        let score: i32 = play(lines, strategy, seed.wrapping_add(run as u64));
+        *histogram.entry(score).or_insert(0) += 1;
+        total += score as i64;
+    }
+    ScoreDistribution {
+        strategy,
+        runs,
+        min: histogram.keys().next().copied().unwrap_or(0),
+        max: histogram.keys().next_back().copied().unwrap_or(0),
+        mean: if runs > 0 { total as f64 / runs as f64 } else { 0.0 },
+        histogram,
+    }
+}
+
+pub fn tournament(lines: &[(i32, i32)], runs: usize, seed: u64) -> Vec<ScoreDistribution> {
+    /*
+     Simulate every strategy against the same guide with the same seeds.
+     */
+    ALL_STRATEGIES.iter().map(|each| simulate(lines, *each, runs, seed)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follow_guide() {
+        let lines = read_lines("day02_input_short.txt");
+        assert_eq!(play(&lines, Strategy::FollowGuide, 0), 15);
+    }
+
+    #[test]
+    fn test_always_win() {
+        let lines = read_lines("day02_input_short.txt");
+        assert_eq!(play(&lines, Strategy::AlwaysWin, 0), 24);
+    }
+
+    #[test]
+    fn test_maximum_score() {
+        let lines = read_lines("day02_input_short.txt");
+        assert_eq!(maximum_score(&lines), 24);
+        let lines = read_lines("day02_input.txt");
+        assert_eq!(maximum_score(&lines), play(&lines, Strategy::AlwaysWin, 0));
+    }
+
+    #[test]
+    fn test_random_is_reproducible() {
+        let lines = read_lines("day02_input.txt");
+        assert_eq!(play(&lines, Strategy::Random, 7), play(&lines, Strategy::Random, 7));
+        assert!(play(&lines, Strategy::Random, 7) <= maximum_score(&lines));
+    }
+
+    #[test]
+    fn test_frequency_counter_exploits_repetition() {
+        // an opponent who always plays rock is beaten from the second round on
+        let lines: Vec<(i32, i32)> = vec![(0, 0); 100];
+        assert!(play(&lines, Strategy::FrequencyCounter, 3) >= 99 * 8);
+    }
+
+    #[test]
+    fn test_simulate() {
+        let lines = read_lines("day02_input_short.txt");
+        let distribution = simulate(&lines, Strategy::Random, 200, 2022);
+        assert_eq!(distribution.histogram.values().sum::<usize>(), 200);
+        assert!(distribution.min as f64 <= distribution.mean);
+        assert!(distribution.mean <= distribution.max as f64);
+        assert!(distribution.max <= 24);
+        let distribution = simulate(&lines, Strategy::FollowGuide, 50, 2022);
+        assert_eq!((distribution.min, distribution.max), (15, 15));
+    }
+
+    #[test]
+    fn test_tournament() {
+        let lines = read_lines("day02_input_short.txt");
+        let results = tournament(&lines, 10, 1);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[1].strategy, Strategy::AlwaysWin);
+        assert_eq!(results[1].mean, 24.0);
+    }
+}
+
+pub fn main() {
+    let result = read_lines("day02_input.txt");
+    println!("Day 2 (tournament simulator):");
+    for each in tournament(&result, 1000, 2022) {
+        println!("{}", each);
+    }
+    println!("The maximum achievable score is: {}", maximum_score(&result));
+    println!();
+}
@@ -1,5 +1,6 @@
 use std::fs;
-use std::collections::HashSet;
+use std::fmt;
+use std::collections::HashMap;
 
 fn read_lines(filename: &str) -> Vec<String> {
     /*
@@ -15,37 +16,216 @@ fn read_lines(filename: &str) -> Vec<String> {
     lines
 }
 
-fn process_lines(lines: &Vec<String>) -> i32 {
+#[derive(Debug, PartialEq)]
+pub enum RucksackError {
+    InvalidItem { line: usize, item: char },
+    UnevenCompartments { line: usize, length: usize },
+    InvalidGroupSize,
+    PartialGroup { first_line: usize, size: usize, group_size: usize },
+    NoSharedItem { first_line: usize, last_line: usize },
+    MultipleSharedItems { first_line: usize, last_line: usize, items: Vec<char> },
+    DuplicateItem { item: char },
+    TooManyItems { size: usize },
+}
+
+impl fmt::Display for RucksackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RucksackError::InvalidItem { line, item } =>
+                write!(f, "line {}: '{}' is not a valid item", line, item),
+            RucksackError::UnevenCompartments { line, length } =>
+                write!(f, "line {}: {} items can't be split into two equal compartments", line, length),
+            RucksackError::InvalidGroupSize =>
+                write!(f, "the group size must be at least 1"),
+            RucksackError::PartialGroup { first_line, size, group_size } =>
+                write!(f, "line {}: the last group only has {} of {} rucksacks", first_line, size, group_size),
+            RucksackError::NoSharedItem { first_line, last_line } =>
+                write!(f, "{}: no item is shared", describe_lines(*first_line, *last_line)),
+            RucksackError::MultipleSharedItems { first_line, last_line, items } =>
+                write!(f, "{}: more than one item is shared: {}", describe_lines(*first_line, *last_line),
+                    items.iter().collect::<String>()),
+            RucksackError::DuplicateItem { item } =>
+                write!(f, "'{}' appears more than once in the priority table", item),
+            RucksackError::TooManyItems { size } =>
+                write!(f, "the priority table has {} items, but at most 64 are supported", size),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SharedItem {
+    pub first_line: usize,
+    pub last_line: usize,
+    pub item: char,
+    pub priority: i32,
+}
+
+impl fmt::Display for SharedItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: shared item '{}' (priority {})", describe_lines(self.first_line, self.last_line),
+            self.item, self.priority)
+    }
+}
+
+fn describe_lines(first_line: usize, last_line: usize) -> String {
+    if first_line == last_line {
+        format!("line {}", first_line)
+    }else {
+        format!("lines {}-{}", first_line, last_line)
+    }
+}
+
+pub struct Priority {
+    // entries[bit] is the item stored at that bit of a mask, and its priority
+    entries: Vec<(char, i32)>,
+    bits: HashMap<char, u32>,
+}
+
+impl Priority {
+    pub fn new(table: &[(char, i32)]) -> Result<Priority, RucksackError> {
+        /*
+        Build a priority scheme from a table of (item, priority) pairs. Each
+        item gets its own bit in a u64 mask, so a table can hold at most 64
+        distinct items.
+        */
+        if table.len() > 64 {
+            return Err(RucksackError::TooManyItems { size: table.len() });
+        }
+        let mut bits: HashMap<char, u32> = HashMap::new();
+        for (bit, (item, _priority)) in table.iter().enumerate() {
+            if bits.insert(*item, bit as u32).is_some() {
+                return Err(RucksackError::DuplicateItem { item: *item });
+            }
+        }
+        Ok(Priority { entries: table.to_vec(), bits })
+    }
+
+    pub fn from_alphabet(alphabet: &str) -> Result<Priority, RucksackError> {
+        /*
+        Build a priority scheme where the first character of the alphabet has
+        priority 1, the second has priority 2, and so on.
+        */
+        let table: Vec<(char, i32)> = alphabet.chars().zip(1..).collect();
+        Priority::new(&table)
+    }
+
+    pub fn aoc() -> Priority {
+        /*
+        Items a-z have priorities 1 through 26, and A-Z have priorities 27
+        through 52.
+        */
+        Priority::from_alphabet("abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap()
+    }
+
+    pub fn priority(&self, item: char) -> Option<i32> {
+        self.bits.get(&item).map(|bit| self.entries[*bit as usize].1)
+    }
+
+    fn bit(&self, item: char) -> Option<u32> {
+        self.bits.get(&item).copied()
+    }
+
+    fn item(&self, bit: u32) -> char {
+        self.entries[bit as usize].0
+    }
+
+    fn items_in(&self, mask: u64) -> Vec<char> {
+        (0..self.entries.len() as u32).filter(|bit| mask & (1 << bit) != 0).map(|bit| self.item(bit)).collect()
+    }
+}
+
+impl Default for Priority {
+    fn default() -> Priority {
+        Priority::aoc()
+    }
+}
+
+fn item_mask(items: &str, line: usize, priority: &Priority) -> Result<u64, RucksackError> {
+    /*
+    Represent a set of items as a mask, where each item's bit is set if that
+    item is present.
+    */
+    let mut mask: u64 = 0;
+    for item in items.chars() {
+        match priority.bit(item) {
+            Some(bit) => mask |= 1 << bit,
+            None => return Err(RucksackError::InvalidItem { line, item }),
+        }
+    }
+    Ok(mask)
+}
+
+fn shared_item(masks: &[u64], first_line: usize, last_line: usize, priority: &Priority) -> Result<SharedItem, RucksackError> {
+    /*
+    Intersect the masks, and return the one item they all have in common.
+    */
+    let common: u64 = masks.iter().fold(u64::MAX, |acc, each| acc & each);
+    match common.count_ones() {
+        0 => Err(RucksackError::NoSharedItem { first_line, last_line }),
+        1 => {
+            let item: char = priority.item(common.trailing_zeros());
+            Ok(SharedItem { first_line, last_line, item, priority: priority.priority(item).unwrap() })
+        },
+        _ => Err(RucksackError::MultipleSharedItems { first_line, last_line, items: priority.items_in(common) }),
+    }
+}
+
+pub fn analyze_rucksacks(lines: &[String], priority: &Priority) -> Result<Vec<SharedItem>, RucksackError> {
+    /*
+     Find the item shared between the two compartments of each rucksack. Line
+     numbers in the results and errors start at 1.
+     */
+    let mut result: Vec<SharedItem> = Vec::new();
+    for (index, each_line) in lines.iter().enumerate() {
+        let line: usize = index + 1;
+        let length: usize = each_line.chars().count();
+        if length % 2 != 0 {
+            return Err(RucksackError::UnevenCompartments { line, length });
+        }
+        // split on a character boundary, so that non-ASCII input is reported
+        // as an invalid item instead of panicking here
+        let middle: usize = each_line.char_indices().nth(length / 2).map_or(each_line.len(), |(byte, _)| byte);
+        let (half1, half2) = each_line.split_at(middle);
+        let masks: [u64; 2] = [item_mask(half1, line, priority)?, item_mask(half2, line, priority)?];
+        result.push(shared_item(&masks, line, line, priority)?);
+    }
+    Ok(result)
+}
+
+pub fn analyze_groups(lines: &[String], group_size: usize, priority: &Priority) -> Result<Vec<SharedItem>, RucksackError> {
+    /*
+     Find the item shared between every rucksack in each consecutive group of
+     group_size rucksacks. A trailing group with fewer rucksacks is an error.
+     */
+    if group_size == 0 {
+        return Err(RucksackError::InvalidGroupSize);
+    }
+    let mut result: Vec<SharedItem> = Vec::new();
+    for (group_index, each_group) in lines.chunks(group_size).enumerate() {
+        let first_line: usize = group_index * group_size + 1;
+        if each_group.len() < group_size {
+            return Err(RucksackError::PartialGroup { first_line, size: each_group.len(), group_size });
+        }
+        let mut masks: Vec<u64> = Vec::new();
+        for (offset, each_line) in each_group.iter().enumerate() {
+            masks.push(item_mask(each_line, first_line + offset, priority)?);
+        }
+        result.push(shared_item(&masks, first_line, first_line + group_size - 1, priority)?);
+    }
+    Ok(result)
+}
+
+fn process_lines(lines: &[String]) -> Result<i32, RucksackError> {
     /*
      Given a list of strings, representing the contents of the elves'
      rucksacks, add up the priorities of the rucksacks.
      
      See Part 1 of https://adventofcode.com/2022/day/3
      */
-    let mut priority_sum: i32 = 0;
-    for each_line in lines {
-        let (half1, half2) = each_line.split_at(each_line.len() / 2);
-        //println!("First half is: {}", half1);
-        //println!("Second half is: {}", half2);
-        let unique: HashSet<char> = half1.chars().collect();
-        let mut common: char = 'a';
-        for each in unique.intersection(&half2.chars().collect::<HashSet<char>>()) {
-            common = *each;
-            break;
-        }
-        let mut score: i32 = i32::try_from(common as u32).unwrap();
-        if score < i32::try_from('a' as u32).unwrap() {
-            score = score + 27 - i32::try_from('A' as u32).unwrap();
-        }else {
-            score = score + 1 - i32::try_from('a' as u32).unwrap();
-        }
-        //println!("Shared character is {}, with a score of {}.", common, score);
-        priority_sum += score;
-    }
-    priority_sum
+    Ok(analyze_rucksacks(lines, &Priority::default())?.iter().map(|each| each.priority).sum())
 }
 
-fn process_lines2(lines: &Vec<String>) -> i32 {
+fn process_lines2(lines: &[String]) -> Result<i32, RucksackError> {
     /*
      Given a list of strings, representing the contents of the elves'
      rucksacks, find the shared items between every group of three 
@@ -53,29 +233,7 @@ fn process_lines2(lines: &Vec<String>) -> i32 {
      
      See Part 2 of https://adventofcode.com/2022/day/3
      */
-    let mut priority_sum: i32 = 0;
-    for each_group in lines.rchunks(3) {
-        let mut lines: Vec<String> = Vec::new();
-        lines.push(each_group[0].clone());
-        lines.push(each_group[1].clone());
-        lines.push(each_group[2].clone());
-        let unique: HashSet<char> = lines[0].chars().collect();
-        let mut common: char = ' ';
-        for each_common in unique.intersection(&lines[1].chars().collect::<HashSet<char>>()) {
-            if lines[2].chars().collect::<Vec<char>>().contains(each_common) {
-                common = *each_common;
-            }
-        }
-        let mut score: i32 = i32::try_from(common as u32).unwrap();
-        if score < i32::try_from('a' as u32).unwrap() {
-            score = score + 27 - i32::try_from('A' as u32).unwrap();
-        }else {
-            score = score + 1 - i32::try_from('a' as u32).unwrap();
-        }
-        //println!("Shared character is {}, with a score of {}.", common, score);
-        priority_sum += score;
-    }
-    priority_sum
+    Ok(analyze_groups(lines, 3, &Priority::default())?.iter().map(|each| each.priority).sum())
 }
 
 #[cfg(test)]
@@ -85,20 +243,99 @@ mod tests {
     #[test]
     fn test_process_lines() {
         let result = read_lines("day03_input_short.txt");
-        assert_eq!(process_lines(&result), 157);
+        assert_eq!(process_lines(&result), Ok(157));
     }
 
     #[test]
     fn test_process_lines2() {
         let result = read_lines("day03_input_short.txt");
-        assert_eq!(process_lines2(&result), 70);
+        assert_eq!(process_lines2(&result), Ok(70));
+    }
+
+    #[test]
+    fn test_analyze_rucksacks() {
+        let result = read_lines("day03_input_short.txt");
+        let shared: Vec<char> = analyze_rucksacks(&result, &Priority::default()).unwrap().iter().map(|each| each.item).collect();
+        assert_eq!(shared, vec!['p', 'L', 'P', 'v', 't', 's']);
+        assert_eq!(analyze_rucksacks(&result, &Priority::default()).unwrap()[1].to_string(), "line 2: shared item 'L' (priority 38)");
+    }
+
+    #[test]
+    fn test_analyze_groups() {
+        let result = read_lines("day03_input_short.txt");
+        let groups = analyze_groups(&result, 3, &Priority::default()).unwrap();
+        assert_eq!(groups.iter().map(|each| each.item).collect::<String>(), "rZ");
+        assert_eq!(groups[1].to_string(), "lines 4-6: shared item 'Z' (priority 52)");
+        // every rucksack on its own has many "shared" items
+        assert!(matches!(analyze_groups(&result, 1, &Priority::default()),
+            Err(RucksackError::MultipleSharedItems { first_line: 1, last_line: 1, .. })));
+    }
+
+    #[test]
+    fn test_partial_group() {
+        let result = read_lines("day03_input_short.txt");
+        assert_eq!(analyze_groups(&result[..5], 3, &Priority::default()),
+            Err(RucksackError::PartialGroup { first_line: 4, size: 2, group_size: 3 }));
+        assert_eq!(analyze_groups(&result, 0, &Priority::default()), Err(RucksackError::InvalidGroupSize));
+    }
+
+    #[test]
+    fn test_rucksack_errors() {
+        let lines: Vec<String> = vec!["abcd".to_string()];
+        assert_eq!(analyze_rucksacks(&lines, &Priority::default()), Err(RucksackError::NoSharedItem { first_line: 1, last_line: 1 }));
+        let lines: Vec<String> = vec!["aBaB".to_string()];
+        assert_eq!(analyze_rucksacks(&lines, &Priority::default()),
+            Err(RucksackError::MultipleSharedItems { first_line: 1, last_line: 1, items: vec!['a', 'B'] }));
+        let lines: Vec<String> = vec!["abcab".to_string()];
+        assert_eq!(analyze_rucksacks(&lines, &Priority::default()), Err(RucksackError::UnevenCompartments { line: 1, length: 5 }));
+        let lines: Vec<String> = vec!["ab1a".to_string()];
+        assert_eq!(analyze_rucksacks(&lines, &Priority::default()), Err(RucksackError::InvalidItem { line: 1, item: '1' }));
+        let lines: Vec<String> = vec!["aé".to_string()];
+        assert_eq!(analyze_rucksacks(&lines, &Priority::default()), Err(RucksackError::InvalidItem { line: 1, item: 'é' }));
+    }
+
+    #[test]
+    fn test_priority() {
+        let priority = Priority::default();
+        assert_eq!(priority.priority('a'), Some(1));
+        assert_eq!(priority.priority('z'), Some(26));
+        assert_eq!(priority.priority('A'), Some(27));
+        assert_eq!(priority.priority('Z'), Some(52));
+        assert_eq!(priority.priority('0'), None);
+        assert_eq!(Priority::from_alphabet("abca").err(), Some(RucksackError::DuplicateItem { item: 'a' }));
+        let too_many: String = (0..65).map(|each| char::from_u32(0x100 + each).unwrap()).collect();
+        assert_eq!(Priority::from_alphabet(&too_many).err(), Some(RucksackError::TooManyItems { size: 65 }));
+    }
+
+    #[test]
+    fn test_custom_priorities() {
+        // digits, scored by their face value
+        let digits = Priority::new(&[('0', 10), ('1', 1), ('2', 2), ('3', 3), ('4', 4),
+            ('5', 5), ('6', 6), ('7', 7), ('8', 8), ('9', 9)]).unwrap();
+        let lines: Vec<String> = vec!["1230".to_string(), "4560".to_string(), "0990".to_string()];
+        let shared = analyze_rucksacks(&lines[..2], &digits);
+        assert_eq!(shared, Err(RucksackError::NoSharedItem { first_line: 1, last_line: 1 }));
+        let shared = analyze_groups(&lines, 3, &digits).unwrap();
+        assert_eq!((shared[0].item, shared[0].priority), ('0', 10));
+        // Greek letters in alphabetical order
+        let greek = Priority::from_alphabet("αβγδεζηθικλμνξοπρστυφχψω").unwrap();
+        let lines: Vec<String> = vec!["αβγδβω".to_string()];
+        let shared = analyze_rucksacks(&lines, &greek).unwrap();
+        assert_eq!((shared[0].item, shared[0].priority), ('β', 2));
+        assert_eq!(analyze_rucksacks(&lines, &Priority::default()), Err(RucksackError::InvalidItem { line: 1, item: 'α' }));
     }
 }
 
 pub fn main() {
     let result = read_lines("day03_input.txt");
     println!("Day 3:");
-    println!("Part 1 - The sum of the priorities of these items is: {}", process_lines(&result));
-    println!("Part 2 - The sum of the badge priorities is: {}", process_lines2(&result));
+    match process_lines(&result) {
+        Ok(sum) => println!("Part 1 - The sum of the priorities of these items is: {}", sum),
+        Err(error) => println!("Part 1 - Invalid input, {}", error),
+    }
+    match process_lines2(&result) {
+        Ok(sum) => println!("Part 2 - The sum of the badge priorities is: {}", sum),
+        Err(error) => println!("Part 2 - Invalid input, {}", error),
+    }
     println!("");
 }
@@ -1,5 +1,8 @@
 use std::fs;
+use std::cmp::{min, max, Reverse};
+use std::collections::BinaryHeap;
 use std::str::FromStr;
+use crate::interval::{Interval, IntervalSet};
 
 fn read_lines(filename: &str) -> Vec<String> {
     /*
@@ -15,7 +18,15 @@ fn read_lines(filename: &str) -> Vec<String> {
     lines
 }
 
-fn process_lines(lines: &Vec<String>) -> i32 {
+fn parse_line(line: &str) -> (Interval, Interval) {
+    /*
+    Parse a pair of section assignments, such as "2-4,6-8".
+    */
+    let (first, second) = line.split_once(',').unwrap();
+    (Interval::from_str(first).unwrap(), Interval::from_str(second).unwrap())
+}
+
+fn process_lines(lines: &[String]) -> i32 {
     /*
      Given a list of pairs of ranges, count how many ranges in the same pair 
      fully contain each other.
@@ -24,45 +35,16 @@ fn process_lines(lines: &Vec<String>) -> i32 {
      */
     let mut fully_contained: i32 = 0;
     for each_line in lines {
-        let commas: String = each_line.replace("-", ",");
-        let my_strings: Vec<&str> = commas.split(",").collect();
-        let mut numbers: Vec<i32> = Vec::new();
-        for each in my_strings {
-            numbers.push(i32::from_str(each).unwrap());
-        }
-        if numbers[1] - numbers[0] > numbers[3] - numbers[2] {
-            // the first range is bigger, check if it contains the second range
-            if numbers[0] <= numbers[2] && numbers[1] >= numbers[3] {
-                //println!("({},{}) fully contains ({},{})", numbers[0], numbers[1], numbers[2], numbers[3]);
-                fully_contained += 1;
-            }
-        }else {
-            // the second range is bigger.
-            if numbers[2] <= numbers[0] && numbers[3] >= numbers[1] {
-                //println!("({},{}) fully contains ({},{})", numbers[2], numbers[3], numbers[0], numbers[1]);
-                fully_contained += 1;
-            }
+        let (first, second) = parse_line(each_line);
+        if first.contains(&second) || second.contains(&first) {
+            //println!("{} and {} fully contain each other", first, second);
+            fully_contained += 1;
         }
     }
     fully_contained
 }
 
-fn ranges_overlap(a: i32, b: i32, c: i32, d: i32) -> i32 {
-    /*
-    Determine if the range (a, b) overlaps with (c, d) at all.
-    */
-    let mut overlap = 0;
-    if (a <= c && c <= b) ||
-        (a <= d && d <= b) ||
-        (c <= a && a <= d) ||
-        (c <= b && b <= d) {
-            //println!("({}, {}) and ({}, {}) overlap", a, b, c, d);
-            overlap += 1;
-        }
-    overlap
-}
-
-fn process_lines2(lines: &Vec<String>) -> i32 {
+fn process_lines2(lines: &[String]) -> i32 {
     /*
      Given a list of pairs of ranges, count how many pairs overlap at all.
      
@@ -70,33 +52,113 @@ fn process_lines2(lines: &Vec<String>) -> i32 {
      */
     let mut overlaps: i32 = 0;
     for each_line in lines {
-        let commas: String = each_line.replace("-", ",");
-        let my_strings: Vec<&str> = commas.split(",").collect();
-        let mut numbers: Vec<i32> = Vec::new();
-        for each in my_strings {
-            numbers.push(i32::from_str(each).unwrap());
+        let (first, second) = parse_line(each_line);
+        if first.overlaps(&second) {
+            overlaps += 1;
         }
-        overlaps += ranges_overlap(numbers[0], numbers[1], numbers[2], numbers[3]);
     }
+    overlaps
+}
 
+fn parse_assignments(lines: &[String]) -> Vec<Interval> {
     /*
-    // Defunct version. This part of the function solved a much harder 
-    // problem, checking each pair if it overlaps with any other pairs.
-    let mut pairs: Vec<Vec<i32>> = Vec::new();
-    for (index, each_pair) in pairs.iter().enumerate() {
-        let (range_1_start, range_1_end, range_2_start, range_2_end) 
-            = (pairs[index][0], pairs[index][1], pairs[index][2], pairs[index][3]);
-        for other_index in index+1..pairs.len() {
-            let (a, b, c, d) = (pairs[other_index][0], pairs[other_index][1], 
-                pairs[other_index][2], pairs[other_index][3]);
-            println!("Before: {}", contained);
-            contained += (ranges_overlap(range_1_start, range_1_end, a, b) | ranges_overlap(range_1_start, range_1_end, c, d)
-                | ranges_overlap(range_2_start, range_2_end, a, b) | ranges_overlap(range_2_start, range_2_end, c, d));
-            println!("After: {}", contained);
+    Flatten the pairs into one list of section assignments, so that elf n is
+    the nth range in the input (counting from 0), regardless of which pair it
+    was in.
+    */
+    let mut assignments: Vec<Interval> = Vec::new();
+    for each_line in lines {
+        let (first, second) = parse_line(each_line);
+        assignments.push(first);
+        assignments.push(second);
+    }
+    assignments
+}
+
+fn overlapping_pairs(assignments: &[Interval]) -> Vec<(usize, usize)> {
+    /*
+    Find every pair of elves, across all of the pairs in the input, whose
+    assignments overlap. This is the problem I accidentally solved the first
+    time around, done properly with a sweep line: visit the assignments in
+    order of their first section, and keep a heap of the assignments that are
+    still open, ordered by their last section. Every assignment that's still
+    open when a new one starts overlaps with it, so this takes
+    O(n log n + k) time for k overlapping pairs instead of O(n^2).
+
+    Each pair is returned as (lower elf, higher elf), sorted.
+    */
+    let mut order: Vec<usize> = (0..assignments.len()).collect();
+    order.sort_by_key(|each| assignments[*each].start());
+    let mut open: BinaryHeap<Reverse<(i64, usize)>> = BinaryHeap::new();
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for elf in order {
+        let sections: Interval = assignments[elf];
+        // close everything that ended before this assignment starts
+        while let Some(Reverse((end, _))) = open.peek() {
+            if *end >= sections.start() {
+                break;
+            }
+            open.pop();
+        }
+        for Reverse((_, other)) in open.iter() {
+            pairs.push((min(elf, *other), max(elf, *other)));
         }
-    }*/
+        open.push(Reverse((sections.end(), elf)));
+    }
+    pairs.sort();
+    pairs
+}
 
-    overlaps
+fn section_coverage(assignments: &[Interval]) -> Vec<(Interval, usize)> {
+    /*
+    Count how many elves cover each section, as a list of runs of sections
+    with the same count. Sections nobody covers are left out.
+
+    Each assignment becomes two events, +1 at its first section and -1 just
+    past its last section, and a sweep over the sorted events keeps a running
+    count. The events are kept as i128, because the section past an
+    assignment that runs to i64::MAX doesn't fit in an i64.
+    */
+    let mut events: Vec<(i128, i64)> = Vec::new();
+    for each in assignments {
+        events.push((each.start() as i128, 1));
+        events.push((each.end() as i128 + 1, -1));
+    }
+    events.sort();
+    let mut coverage: Vec<(Interval, usize)> = Vec::new();
+    let mut count: i64 = 0;
+    let mut index: usize = 0;
+    while index < events.len() {
+        let section: i128 = events[index].0;
+        while index < events.len() && events[index].0 == section {
+            count += events[index].1;
+            index += 1;
+        }
+        if count > 0 && index < events.len() {
+            // a later event exists, so both ends of the run fit in an i64
+            let run: Interval = Interval::new(section as i64, (events[index].0 - 1) as i64);
+            // one elf stopping where another starts leaves the count unchanged
+            match coverage.last_mut() {
+                Some((last, last_count)) if *last_count == count as usize && last.end() + 1 == run.start() =>
+                    *last = last.union(&run).unwrap(),
+                _ => coverage.push((run, count as usize)),
+            }
+        }
+    }
+    coverage
+}
+
+fn most_contested(coverage: &[(Interval, usize)]) -> Option<(usize, Vec<Interval>)> {
+    /*
+    Return the highest number of elves covering any one section, and the runs
+    of sections with that many elves.
+    */
+    let highest: usize = coverage.iter().map(|(_, count)| *count).max()?;
+    let sections: Vec<Interval> = coverage.iter()
+        .filter(|(_, count)| *count == highest)
+        .map(|(sections, _)| *sections)
+        .collect();
+    Some((highest, sections))
 }
 
 #[cfg(test)]
@@ -114,6 +176,61 @@ mod tests {
         let result = read_lines("day04_input_short.txt");
         assert_eq!(process_lines2(&result), 4);
     }
+
+    fn brute_force_pairs(assignments: &[Interval]) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<(usize, usize)> = Vec::new();
+        for first in 0..assignments.len() {
+            for second in first+1..assignments.len() {
+                if assignments[first].overlaps(&assignments[second]) {
+                    pairs.push((first, second));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn test_overlapping_pairs_short() {
+        let assignments = parse_assignments(&read_lines("day04_input_short.txt"));
+        assert_eq!(assignments.len(), 12);
+        let pairs = overlapping_pairs(&assignments);
+        assert!(pairs.contains(&(0, 2)));
+        assert!(!pairs.contains(&(0, 1)));
+        assert_eq!(pairs, brute_force_pairs(&assignments));
+    }
+
+    #[test]
+    fn test_overlapping_pairs_full() {
+        let assignments = parse_assignments(&read_lines("day04_input.txt"));
+        assert_eq!(overlapping_pairs(&assignments), brute_force_pairs(&assignments));
+    }
+
+    #[test]
+    fn test_section_coverage() {
+        let assignments = parse_assignments(&read_lines("day04_input_short.txt"));
+        let coverage = section_coverage(&assignments);
+        assert_eq!(coverage, vec![(Interval::new(2, 2), 4), (Interval::new(3, 3), 5), (Interval::new(4, 5), 7),
+            (Interval::new(6, 6), 8), (Interval::new(7, 7), 6), (Interval::new(8, 8), 4), (Interval::new(9, 9), 1)]);
+        assert_eq!(most_contested(&coverage), Some((8, vec![Interval::new(6, 6)])));
+        assert_eq!(most_contested(&[]), None);
+    }
+
+    #[test]
+    fn test_section_coverage_gaps() {
+        let assignments = vec![Interval::new(1, 2), Interval::new(5, 6), Interval::new(2, 2), Interval::new(8, 9)];
+        assert_eq!(section_coverage(&assignments), vec![(Interval::new(1, 1), 1), (Interval::new(2, 2), 2),
+            (Interval::new(5, 6), 1), (Interval::new(8, 9), 1)]);
+        assert_eq!(most_contested(&section_coverage(&assignments)), Some((2, vec![Interval::new(2, 2)])));
+    }
+
+    #[test]
+    fn test_section_coverage_to_the_end() {
+        let assignments = parse_assignments(&["1-9223372036854775807,9223372036854775800-9223372036854775807".to_string()]);
+        assert_eq!(section_coverage(&assignments), vec![(Interval::new(1, 9223372036854775799), 1),
+            (Interval::new(9223372036854775800, i64::MAX), 2)]);
+        let assignments = vec![Interval::new(i64::MIN, i64::MAX)];
+        assert_eq!(section_coverage(&assignments), vec![(Interval::new(i64::MIN, i64::MAX), 1)]);
+    }
 }
 
 pub fn main() {
@@ -121,5 +238,28 @@ pub fn main() {
     println!("Day 4:");
     println!("Part 1 - The number of fully contained ranges is: {}", process_lines(&result));
     println!("Part 2 - The number of pairs that overlap at all is: {}", process_lines2(&result));
+    let assignments: Vec<Interval> = parse_assignments(&result);
+    let pairs: Vec<(usize, usize)> = overlapping_pairs(&assignments);
+    println!("The number of overlapping assignments across all elves is: {}", pairs.len());
+    let widest: Option<Interval> = pairs.iter()
+        .filter_map(|(first, second)| assignments[*first].intersection(&assignments[*second]))
+        .max_by_key(|shared| shared.len());
+    if let Some(shared) = widest {
+        println!("The widest stretch two elves share is {}", shared);
+    }
+    if let Some((count, sections)) = most_contested(&section_coverage(&assignments)) {
+        let first: i64 = sections[0].start();
+        let elves: usize = assignments.iter().filter(|each| each.contains_point(first)).count();
+        let sections: Vec<String> = sections.iter().map(|each| each.to_string()).collect();
+        println!("The most contested sections are {}, covered by {} elves", sections.join(", "), count);
+        println!("Section {} is in {} assignments", first, elves);
+    }
+    let covered: IntervalSet = assignments.iter().copied().collect();
+    if let (Some(bounds), Some(total)) = (covered.bounds(), covered.coverage()) {
+        println!("The elves cover {} sections between {}, leaving {} gaps", total, bounds, covered.gaps().len());
+        let middle: i64 = ((bounds.start() as i128 + bounds.end() as i128) / 2) as i64;
+        let state: &str = if covered.contains_point(middle) { "covered" } else { "not covered" };
+        println!("The middle section {} is {}", middle, state);
+    }
     println!("");
 }
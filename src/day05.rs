@@ -1,4 +1,5 @@
 use std::fs;
+use std::fmt;
 use std::str::FromStr;
 use regex::Regex;
 
@@ -26,147 +27,636 @@ fn read_lines(filename: &str) -> (Vec<String>, Vec<String>) {
     (lines, moves)
 }
 
-fn process_lines(lines: &Vec<String>, moves: &Vec<String>) -> String {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Move {
+    pub count: usize,
+    // both stacks are 0-based, even though the puzzle input counts from 1
+    pub from: usize,
+    pub to: usize,
+}
+
+pub trait Crane<T> {
     /*
-     Given an initial arrangement of crates and a list of movements for them,
-     return the list of crates on top of each stack.
-     
-     See Part 1 of https://adventofcode.com/2022/day/5
-     */
-    let mut return_value: Vec<char> = Vec::new();
-    let mut stacks: Vec<Vec<char>> = Vec::new();
-    for _index in 0..((lines[0].len() - 3) / 4)+1 {
-        stacks.push(Vec::new());
-    } 
-    // populate the initial states of the stacks
-    for each_line in &lines[0..lines.len()-1] {
-        for each_stack in 0..stacks.len() {
-            //println!("Trying to add {} to stack {}", each_line, each_stack);
-            let this_crate: char = each_line.chars().nth((each_stack*4)+1).unwrap();
-            if this_crate != ' ' {
-                stacks[each_stack].push(this_crate);
-            }
+    A crane model only has to decide how a single move rearranges the crates.
+    Everything else, like parsing and running the move list, is shared.
+    The crate type is a parameter of the trait rather than of the method, so
+    that a crane can be picked at runtime as a `&dyn Crane<T>`.
+    */
+    fn move_crates(&self, stacks: &mut [Vec<T>], each_move: &Move);
+}
+
+pub struct CrateMover9000;
+
+impl<T> Crane<T> for CrateMover9000 {
+    fn move_crates(&self, stacks: &mut [Vec<T>], each_move: &Move) {
+        // one crate at a time, so a multi-crate move reverses their order
+        for _index in 0..each_move.count {
+            let value: T = stacks[each_move.from].pop().unwrap();
+            stacks[each_move.to].push(value);
         }
     }
-    // reverse the stacks
-    for each_stack in 0..stacks.len() {
-        stacks[each_stack].reverse();
+}
+
+pub struct CrateMover9001;
+
+impl<T> Crane<T> for CrateMover9001 {
+    fn move_crates(&self, stacks: &mut [Vec<T>], each_move: &Move) {
+        // all of the crates at once, without changing their order
+        let split_at: usize = stacks[each_move.from].len() - each_move.count;
+        let crates: Vec<T> = stacks[each_move.from].split_off(split_at);
+        stacks[each_move.to].extend(crates);
     }
-    // print the stacks
-    /*for each_stack in 0..stacks.len() {
-        println!("Stack {}: {}", each_stack, stacks[each_stack].iter().collect::<String>());
-    }*/
-    // parse the move set
-    let re = Regex::new(r"^\s*move\s+(\d+)\s+from\s+(\d+)\s+to\s+(\d+)\s*$").unwrap();
-    let mut move_tuples: Vec<(i32, i32, i32)> = Vec::new();
-    for each_move in moves {
-        let move_captures = re.captures(each_move).unwrap();
-        move_tuples.push((i32::from_str(move_captures.get(1).unwrap().as_str()).unwrap(),
-        i32::from_str(move_captures.get(2).unwrap().as_str()).unwrap()-1,
-        i32::from_str(move_captures.get(3).unwrap().as_str()).unwrap()-1));
-        continue;
-    }
-    // carry out the moves
-    for each_move in move_tuples {
-        let mut temp_stack: Vec<char> = Vec::new();
-        for _index in 0..each_move.0 {
-            let value = stacks[each_move.1 as usize].pop().unwrap();
-            //println!("Adding value {}", value);
-            temp_stack.push(value);
+}
+
+pub struct CappedCrane {
+    capacity: usize,
+}
+
+impl CappedCrane {
+    pub fn new(capacity: usize) -> Result<CappedCrane, MoveError> {
+        // a crane that can't lift a single crate would never finish a move
+        if capacity == 0 {
+            return Err(MoveError::ZeroCapacity);
         }
-        for each_crate in 0..temp_stack.len() {
-            stacks[each_move.2 as usize].push(temp_stack[each_crate]);
-            //println!("{}", each_crate);
+        Ok(CappedCrane { capacity })
+    }
+}
+
+impl<T> Crane<T> for CappedCrane {
+    fn move_crates(&self, stacks: &mut [Vec<T>], each_move: &Move) {
+        /*
+        Like the CrateMover 9001, but it can only lift `capacity` crates at a
+        time, so larger moves are split into several smaller lifts.
+        */
+        let mut remaining: usize = each_move.count;
+        while remaining > 0 {
+            let count: usize = remaining.min(self.capacity);
+            CrateMover9001.move_crates(stacks, &Move { count, from: each_move.from, to: each_move.to });
+            remaining -= count;
         }
     }
+}
 
-    // print the stacks again
-    /*for each_stack in 0..stacks.len() {
-        println!("Stack {}: {}", each_stack, stacks[each_stack].iter().collect::<String>());
-    }*/
+#[derive(Debug, PartialEq)]
+pub enum DiagramError {
+    Empty,
+    MissingLabels { line: usize },
+    UnexpectedLabel { expected: usize, found: String },
+    UnexpectedCharacter { line: usize, column: usize, found: char },
+    UnclosedCrate { line: usize, column: usize },
+    EmptyCrate { line: usize, column: usize },
+    MisalignedCrate { line: usize, column: usize },
+    FloatingCrate { line: usize, stack: usize },
+    WideCrate { line: usize, stack: usize, label: String },
+}
 
-    // reverse the stacks, so that the top crate is at index 0 on each stack
-    for each_stack in 0..stacks.len() {
-        stacks[each_stack].reverse();
+impl fmt::Display for DiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagramError::Empty =>
+                write!(f, "the drawing of the stacks is empty"),
+            DiagramError::MissingLabels { line } =>
+                write!(f, "line {}: expected the row of stack numbers", line),
+            DiagramError::UnexpectedLabel { expected, found } =>
+                write!(f, "expected stack number {}, but found '{}'", expected, found),
+            DiagramError::UnexpectedCharacter { line, column, found } =>
+                write!(f, "line {}, column {}: unexpected '{}' outside of a crate", line, column, found),
+            DiagramError::UnclosedCrate { line, column } =>
+                write!(f, "line {}, column {}: crate is missing its closing ']'", line, column),
+            DiagramError::EmptyCrate { line, column } =>
+                write!(f, "line {}, column {}: crate has no label", line, column),
+            DiagramError::MisalignedCrate { line, column } =>
+                write!(f, "line {}, column {}: crate isn't above any stack number", line, column),
+            DiagramError::FloatingCrate { line, stack } =>
+                write!(f, "line {}: crate on stack {} has nothing underneath it", line, stack),
+            DiagramError::WideCrate { line, stack, label } =>
+                write!(f, "line {}: crate [{}] on stack {} has more than one character", line, label, stack),
+        }
     }
+}
 
-    // calculate the final return value
-    for each_stack in 0..stacks.len() {
-        if stacks[each_stack].len() > 0 {
-            return_value.push(stacks[each_stack][0]);
+fn label_columns(line: &str, line_number: usize) -> Result<Vec<usize>, DiagramError> {
+    /*
+    Parse the row of stack numbers under the drawing. The numbers have to be
+    1, 2, 3 and so on, and each stack is found by the column in the middle of
+    its number.
+    */
+    let characters: Vec<char> = line.chars().collect();
+    let mut columns: Vec<usize> = Vec::new();
+    let mut index: usize = 0;
+    while index < characters.len() {
+        if characters[index].is_whitespace() {
+            index += 1;
+            continue;
         }
+        let start: usize = index;
+        while index < characters.len() && !characters[index].is_whitespace() {
+            index += 1;
+        }
+        let label: String = characters[start..index].iter().collect();
+        if usize::from_str(&label).ok() != Some(columns.len() + 1) {
+            if columns.is_empty() {
+                return Err(DiagramError::MissingLabels { line: line_number });
+            }
+            return Err(DiagramError::UnexpectedLabel { expected: columns.len() + 1, found: label });
+        }
+        columns.push((start + index - 1) / 2);
+    }
+    if columns.is_empty() {
+        return Err(DiagramError::MissingLabels { line: line_number });
     }
+    Ok(columns)
+}
+
+pub fn parse_diagram(lines: &[String]) -> Result<Vec<Vec<String>>, DiagramError> {
+    /*
+    Turn the drawing of the stacks into one Vector per stack, with the bottom
+    crate first. The row of stack numbers at the bottom decides how many
+    stacks there are and where they are, so the drawing can have more than 9
+    stacks, crates with labels longer than one character, and lines with the
+    trailing whitespace trimmed off. A crate belongs to the stack whose number
+    is underneath the middle of it.
 
-    return_value.iter().collect::<String>()
+    Line and column numbers in the errors start at 1.
+    */
+    let (label_line, crate_lines) = match lines.split_last() {
+        Some(split) => split,
+        None => return Err(DiagramError::Empty),
+    };
+    let columns: Vec<usize> = label_columns(label_line, lines.len())?;
+    let mut stacks: Vec<Vec<String>> = vec![Vec::new(); columns.len()];
+    // the line of the highest crate seen so far on each stack
+    let mut first_seen: Vec<Option<usize>> = vec![None; columns.len()];
+    for (index, each_line) in crate_lines.iter().enumerate() {
+        let line: usize = index + 1;
+        let characters: Vec<char> = each_line.chars().collect();
+        let mut in_row: Vec<bool> = vec![false; columns.len()];
+        let mut column: usize = 0;
+        while column < characters.len() {
+            if characters[column].is_whitespace() {
+                column += 1;
+                continue;
+            }
+            if characters[column] != '[' {
+                return Err(DiagramError::UnexpectedCharacter { line, column: column + 1, found: characters[column] });
+            }
+            let close: usize = match characters[column..].iter().position(|c| *c == ']') {
+                Some(offset) => column + offset,
+                None => return Err(DiagramError::UnclosedCrate { line, column: column + 1 }),
+            };
+            let label: String = characters[column+1..close].iter().collect();
+            if label.trim().is_empty() {
+                return Err(DiagramError::EmptyCrate { line, column: column + 1 });
+            }
+            let stack: usize = match columns.iter().position(|middle| column <= *middle && *middle <= close) {
+                Some(stack) => stack,
+                None => return Err(DiagramError::MisalignedCrate { line, column: column + 1 }),
+            };
+            stacks[stack].push(label);
+            in_row[stack] = true;
+            column = close + 1;
+        }
+        for stack in 0..columns.len() {
+            if in_row[stack] {
+                first_seen[stack].get_or_insert(line);
+            }else if let Some(above) = first_seen[stack] {
+                return Err(DiagramError::FloatingCrate { line: above, stack: stack + 1 });
+            }
+        }
+    }
+    // the drawing lists each stack from the top down
+    for each_stack in stacks.iter_mut() {
+        each_stack.reverse();
+    }
+    Ok(stacks)
 }
 
-fn process_lines2(lines: &Vec<String>, moves: &Vec<String>) -> String {
+fn parse_stacks(lines: &[String]) -> Result<Vec<Vec<char>>, DiagramError> {
     /*
-     This is the same as process_lines(), except when multiple crates are moved
-     in the same step, they're moved together without changing their order.
-     
-     See Part 2 of https://adventofcode.com/2022/day/5
-     */
-    let mut return_value: Vec<char> = Vec::new();
+    The puzzle input only has single-character crates, which are easier to
+    work with as chars.
+    */
     let mut stacks: Vec<Vec<char>> = Vec::new();
-    for _index in 0..((lines[0].len() - 3) / 4)+1 {
-        stacks.push(Vec::new());
-    } 
-    // populate the initial states of the stacks
-    for each_line in &lines[0..lines.len()-1] {
-        for each_stack in 0..stacks.len() {
-            //println!("Trying to add {} to stack {}", each_line, each_stack);
-            let this_crate: char = each_line.chars().nth((each_stack*4)+1).unwrap();
-            if this_crate != ' ' {
-                stacks[each_stack].push(this_crate);
+    for (stack, each_stack) in parse_diagram(lines)?.iter().enumerate() {
+        let mut crates: Vec<char> = Vec::new();
+        for (height, label) in each_stack.iter().enumerate() {
+            let mut characters = label.chars();
+            match (characters.next(), characters.next()) {
+                (Some(value), None) => crates.push(value),
+                _ => return Err(DiagramError::WideCrate {
+                    line: lines.len() - 1 - height, stack: stack + 1, label: label.clone() }),
             }
         }
+        stacks.push(crates);
     }
-    // reverse the stacks
-    for each_stack in 0..stacks.len() {
-        stacks[each_stack].reverse();
+    Ok(stacks)
+}
+
+pub fn render_stacks<T: fmt::Display>(stacks: &[Vec<T>]) -> Vec<String> {
+    /*
+    The inverse of parse_diagram(): draw the stacks in the same format as the
+    puzzle input, including the row of stack numbers. Every stack gets the
+    same width, which is 3 characters for single-character crates and fewer
+    than 10 stacks, exactly like the puzzle input.
+    */
+    let labels: Vec<Vec<String>> = stacks.iter()
+        .map(|each_stack| each_stack.iter().map(|each| format!("[{}]", each)).collect())
+        .collect();
+    let width: usize = labels.iter().flatten().map(|each| each.chars().count())
+        .chain((1..=stacks.len()).map(|each| each.to_string().len()))
+        .max().unwrap_or(3).max(3);
+    let center = |text: &str| -> String {
+        let padding: usize = width - text.chars().count();
+        format!("{}{}{}", " ".repeat(padding / 2), text, " ".repeat(padding - padding / 2))
+    };
+    let height: usize = stacks.iter().map(|each_stack| each_stack.len()).max().unwrap_or(0);
+    let mut lines: Vec<String> = Vec::new();
+    for level in (0..height).rev() {
+        let row: Vec<String> = labels.iter()
+            .map(|each_stack| match each_stack.get(level) {
+                Some(label) => center(label),
+                None => " ".repeat(width),
+            })
+            .collect();
+        lines.push(row.join(" "));
     }
-    // parse the move set
+    let numbers: Vec<String> = (1..=stacks.len()).map(|each| center(&each.to_string())).collect();
+    lines.push(numbers.join(" "));
+    lines
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MoveError {
+    Malformed { line: usize, text: String },
+    InvalidStack { line: usize, each_move: Move, stack: usize },
+    NotEnoughCrates { line: usize, each_move: Move, stack: usize, available: usize },
+    NoSuchStep { step: usize, total: usize },
+    ZeroCapacity,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::Malformed { line, text } =>
+                write!(f, "move {}: '{}' doesn't look like 'move 1 from 2 to 3'", line, text),
+            MoveError::InvalidStack { line, each_move, stack } =>
+                write!(f, "move {} ({}): there is no stack {}", line, each_move, stack),
+            MoveError::NotEnoughCrates { line, each_move, stack, available: 0 } =>
+                write!(f, "move {} ({}): stack {} is empty", line, each_move, stack),
+            MoveError::NotEnoughCrates { line, each_move, stack, available } =>
+                write!(f, "move {} ({}): stack {} only has {} crates", line, each_move, stack, available),
+            MoveError::NoSuchStep { step, total } =>
+                write!(f, "there is no step {}, there are only {} moves", step, total),
+            MoveError::ZeroCapacity =>
+                write!(f, "a crane has to be able to lift at least one crate"),
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.from + 1, self.to + 1)
+    }
+}
+
+fn parse_moves(moves: &[String]) -> Result<Vec<Move>, MoveError> {
+    /*
+    Parse the list of moves. Line numbers in the errors count from 1 at the
+    first move.
+    */
     let re = Regex::new(r"^\s*move\s+(\d+)\s+from\s+(\d+)\s+to\s+(\d+)\s*$").unwrap();
-    let mut move_tuples: Vec<(i32, i32, i32)> = Vec::new();
-    for each_move in moves {
-        let move_captures = re.captures(each_move).unwrap();
-        move_tuples.push((i32::from_str(move_captures.get(1).unwrap().as_str()).unwrap(),
-        i32::from_str(move_captures.get(2).unwrap().as_str()).unwrap()-1,
-        i32::from_str(move_captures.get(3).unwrap().as_str()).unwrap()-1));
-        continue;
-    }
-    // carry out the moves
-    for each_move in move_tuples {
-        // get the crates to be moved, without changing their order
-        let temp_stack: Vec<char> = stacks[each_move.1 as usize][stacks[each_move.1 as usize].len()-each_move.0 as usize..].to_vec();
-        // add to destination stack
-        for each_crate in 0..temp_stack.len() {
-            stacks[each_move.2 as usize].push(temp_stack[each_crate]);
-            //println!("{}", each_crate);
+    let mut move_list: Vec<Move> = Vec::new();
+    for (index, each_move) in moves.iter().enumerate() {
+        let malformed = || MoveError::Malformed { line: index + 1, text: each_move.clone() };
+        let move_captures = re.captures(each_move).ok_or_else(malformed)?;
+        let mut numbers: Vec<usize> = Vec::new();
+        for group in 1..=3 {
+            numbers.push(usize::from_str(move_captures.get(group).unwrap().as_str()).map_err(|_| malformed())?);
+        }
+        // stack 0 can't be written as a 0-based index
+        if numbers[1] == 0 || numbers[2] == 0 {
+            return Err(malformed());
+        }
+        move_list.push(Move { count: numbers[0], from: numbers[1] - 1, to: numbers[2] - 1 });
+    }
+    Ok(move_list)
+}
+
+pub fn validate_move<T>(stacks: &[Vec<T>], each_move: &Move, line: usize) -> Result<(), MoveError> {
+    /*
+    Check that a move can be carried out on the given stacks, before any crane
+    tries it.
+    */
+    for stack in [each_move.from, each_move.to] {
+        if stack >= stacks.len() {
+            return Err(MoveError::InvalidStack { line, each_move: *each_move, stack: stack + 1 });
+        }
+    }
+    let available: usize = stacks[each_move.from].len();
+    if available < each_move.count {
+        return Err(MoveError::NotEnoughCrates { line, each_move: *each_move, stack: each_move.from + 1, available });
+    }
+    Ok(())
+}
+
+pub fn simulate<T: Clone>(stacks: &[Vec<T>], moves: &[Move], crane: &dyn Crane<T>) -> Result<Vec<Vec<T>>, MoveError> {
+    /*
+     Carry out the moves with the given crane, and return the final stacks.
+     Each move is checked before it runs, and the first invalid one stops the
+     simulation.
+     */
+    let mut stacks: Vec<Vec<T>> = stacks.to_vec();
+    for (index, each_move) in moves.iter().enumerate() {
+        validate_move(&stacks, each_move, index + 1)?;
+        crane.move_crates(&mut stacks, each_move);
+    }
+    Ok(stacks)
+}
+
+pub struct Session<T, C> {
+    crane: C,
+    moves: Vec<Move>,
+    // history[n] is the stacks after the first n moves, so history[0] is the
+    // starting arrangement. Undoing a move keeps its state around, so that
+    // stepping forward again doesn't have to recompute it.
+    history: Vec<Vec<Vec<T>>>,
+    position: usize,
+}
+
+impl<T: Clone, C: Crane<T>> Session<T, C> {
+    pub fn new(stacks: Vec<Vec<T>>, moves: Vec<Move>, crane: C) -> Session<T, C> {
+        Session { crane, moves, history: vec![stacks], position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        /*
+        The number of moves that have been carried out so far.
+        */
+        self.position
+    }
+
+    pub fn stacks(&self) -> &[Vec<T>] {
+        &self.history[self.position]
+    }
+
+    fn compute_next(&mut self) -> Result<(), MoveError> {
+        /*
+        Work out the state after the next move that hasn't been computed yet.
+        */
+        let line: usize = self.history.len();
+        let mut stacks: Vec<Vec<T>> = self.history[line - 1].clone();
+        validate_move(&stacks, &self.moves[line - 1], line)?;
+        self.crane.move_crates(&mut stacks, &self.moves[line - 1]);
+        self.history.push(stacks);
+        Ok(())
+    }
+
+    pub fn step(&mut self) -> Result<Option<Move>, MoveError> {
+        /*
+        Carry out the next move and return it, or None if every move has
+        already been carried out. An invalid move leaves the stacks as they
+        were.
+        */
+        if self.position == self.moves.len() {
+            return Ok(None);
+        }
+        if self.history.len() == self.position + 1 {
+            self.compute_next()?;
+        }
+        self.position += 1;
+        Ok(Some(self.moves[self.position - 1]))
+    }
+
+    pub fn undo(&mut self) -> Option<Move> {
+        /*
+        Take back the last move, and return it, or None if we're already back
+        at the start.
+        */
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        Some(self.moves[self.position])
+    }
+
+    pub fn state_at(&mut self, step: usize) -> Result<&[Vec<T>], MoveError> {
+        /*
+        Return the stacks after the first `step` moves, without changing the
+        current position.
+        */
+        if step > self.moves.len() {
+            return Err(MoveError::NoSuchStep { step, total: self.moves.len() });
         }
-        // remove from origin stack
-        stacks[each_move.1 as usize] = stacks[each_move.1 as usize][..stacks[each_move.1 as usize].len()-each_move.0 as usize].to_vec();
+        while self.history.len() <= step {
+            self.compute_next()?;
+        }
+        Ok(&self.history[step])
+    }
+
+    pub fn trace(&mut self) -> Result<&[Vec<Vec<T>>], MoveError> {
+        /*
+        Return the stacks before the first move, and after every move.
+        */
+        self.state_at(self.moves.len())?;
+        Ok(&self.history)
     }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+    Diagram(DiagramError),
+    StackCountMismatch { start: usize, target: usize },
+    DifferentCrates,
+    NotEnoughStacks,
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanError::Diagram(error) =>
+                write!(f, "{}", error),
+            PlanError::StackCountMismatch { start, target } =>
+                write!(f, "the start has {} stacks, but the target has {}", start, target),
+            PlanError::DifferentCrates =>
+                write!(f, "the start and the target don't have the same crates"),
+            PlanError::NotEnoughStacks =>
+                write!(f, "a spare stack is needed to dig out a crate, but there isn't one"),
+        }
+    }
+}
+
+impl From<DiagramError> for PlanError {
+    fn from(error: DiagramError) -> PlanError {
+        PlanError::Diagram(error)
+    }
+}
+
+fn settled_height<T: PartialEq>(stack: &[T], target: &[T]) -> usize {
+    /*
+    The number of crates at the bottom of the stack that are already where the
+    target wants them. These never have to move again.
+    */
+    stack.iter().zip(target).take_while(|(current, wanted)| current == wanted).count()
+}
+
+fn spare_stack<T: PartialEq>(stacks: &[Vec<T>], target: &[Vec<T>], exclude: &[usize]) -> Result<usize, PlanError> {
+    /*
+    Pick a stack to dump crates on that are in the way. Stacks that already
+    have misplaced crates on top are the best choice, and finished stacks are
+    the worst, since they'll have to be cleared again.
+    */
+    (0..stacks.len())
+        .filter(|each| !exclude.contains(each))
+        .min_by_key(|each| {
+            let settled: usize = settled_height(&stacks[*each], &target[*each]);
+            if stacks[*each].len() > settled {
+                0
+            }else if settled < target[*each].len() {
+                1
+            }else {
+                2
+            }
+        })
+        .ok_or(PlanError::NotEnoughStacks)
+}
 
-    // print the stacks again
-    for each_stack in 0..stacks.len() {
-        println!("Stack {}: {}", each_stack, stacks[each_stack].iter().collect::<String>());
+fn compress_moves<T: Clone + PartialEq>(start: &[Vec<T>], moves: &[Move], crane: &impl Crane<T>) -> Vec<Move> {
+    /*
+    Merge back-to-back moves between the same two stacks into one move, as
+    long as the crane ends up in the same place either way. For the CrateMover
+    9000, moving 1 crate twice is the same as moving 2 crates, but for the
+    9001 it isn't.
+    */
+    let mut result: Vec<Move> = Vec::new();
+    let mut before_last: Vec<Vec<T>> = start.to_vec();
+    let mut current: Vec<Vec<T>> = start.to_vec();
+    for each_move in moves {
+        let mut next: Vec<Vec<T>> = current.clone();
+        crane.move_crates(&mut next, each_move);
+        if let Some(last) = result.last_mut() {
+            if last.from == each_move.from && last.to == each_move.to {
+                let mut merged: Vec<Vec<T>> = before_last.clone();
+                crane.move_crates(&mut merged, &Move { count: last.count + each_move.count, ..*each_move });
+                if merged == next {
+                    last.count += each_move.count;
+                    current = next;
+                    continue;
+                }
+            }
+        }
+        before_last = current;
+        result.push(*each_move);
+        current = next;
     }
+    result
+}
+
+pub fn plan_moves<T: Clone + Ord>(start: &[Vec<T>], target: &[Vec<T>], crane: &impl Crane<T>) -> Result<Vec<Move>, PlanError> {
+    /*
+    Work out a short list of moves that turns the start arrangement into the
+    target with the given crane. This isn't guaranteed to be the shortest
+    possible list, but it never moves a crate that's already in its final
+    spot.
 
-    // reverse the stacks, so that the top crate is at index 0 on each stack
-    for each_stack in 0..stacks.len() {
-        stacks[each_stack].reverse();
+    Each round finds a stack that's ready for its next crate, and the copy of
+    that crate with the fewest crates on top of it. Anything on top is moved
+    to a spare stack in one go, then the crate is moved over. Every round
+    puts one more crate in its final spot, so this always finishes, but it
+    needs at least three stacks whenever some digging is involved.
+    */
+    if start.len() != target.len() {
+        return Err(PlanError::StackCountMismatch { start: start.len(), target: target.len() });
+    }
+    let mut start_crates: Vec<&T> = start.iter().flatten().collect();
+    let mut target_crates: Vec<&T> = target.iter().flatten().collect();
+    start_crates.sort();
+    target_crates.sort();
+    if start_crates != target_crates {
+        return Err(PlanError::DifferentCrates);
     }
 
-    // calculate the final return value
-    for each_stack in 0..stacks.len() {
-        if stacks[each_stack].len() > 0 {
-            return_value.push(stacks[each_stack][0]);
+    let mut stacks: Vec<Vec<T>> = start.to_vec();
+    let mut moves: Vec<Move> = Vec::new();
+    let mut carry_out = |stacks: &mut Vec<Vec<T>>, each_move: Move| {
+        crane.move_crates(stacks, &each_move);
+        moves.push(each_move);
+    };
+    while stacks != target {
+        let settled: Vec<usize> = (0..stacks.len()).map(|each| settled_height(&stacks[each], &target[each])).collect();
+        // (stack to build on, stack to take from, crates in the way)
+        let mut best: Option<(usize, usize, usize)> = None;
+        for to in 0..stacks.len() {
+            if stacks[to].len() != settled[to] || settled[to] == target[to].len() {
+                continue;
+            }
+            let wanted: &T = &target[to][settled[to]];
+            for from in (0..stacks.len()).filter(|each| *each != to) {
+                if let Some(height) = (settled[from]..stacks[from].len()).rev().find(|each| stacks[from][*each] == *wanted) {
+                    let in_the_way: usize = stacks[from].len() - 1 - height;
+                    if best.is_none_or(|(_, _, fewest)| in_the_way < fewest) {
+                        best = Some((to, from, in_the_way));
+                    }
+                }
+            }
+        }
+        match best {
+            Some((to, from, in_the_way)) => {
+                if in_the_way > 0 {
+                    let spare: usize = spare_stack(&stacks, target, &[to, from])?;
+                    carry_out(&mut stacks, Move { count: in_the_way, from, to: spare });
+                }
+                carry_out(&mut stacks, Move { count: 1, from, to });
+            },
+            None => {
+                // every unfinished stack has misplaced crates on top, so clear
+                // off the one with the fewest
+                let from: usize = (0..stacks.len())
+                    .filter(|each| settled[*each] < target[*each].len())
+                    .min_by_key(|each| stacks[*each].len() - settled[*each])
+                    .unwrap();
+                let spare: usize = spare_stack(&stacks, target, &[from])?;
+                let count: usize = stacks[from].len() - settled[from];
+                carry_out(&mut stacks, Move { count, from, to: spare });
+            },
         }
     }
+    Ok(compress_moves(start, &moves, crane))
+}
 
-    return_value.iter().collect::<String>()
+pub fn plan_from_diagrams(start: &[String], target: &[String], crane: &impl Crane<String>) -> Result<Vec<String>, PlanError> {
+    /*
+    Plan the moves between two drawings in the puzzle input format, and return
+    them as lines in the puzzle input format as well.
+    */
+    let moves: Vec<Move> = plan_moves(&parse_diagram(start)?, &parse_diagram(target)?, crane)?;
+    Ok(moves.iter().map(|each| each.to_string()).collect())
+}
+
+fn top_crates<T: fmt::Display>(stacks: &[Vec<T>]) -> String {
+    /*
+    Return the crate on top of each stack, skipping empty stacks.
+    */
+    stacks.iter().filter_map(|each_stack| each_stack.last()).map(|each| each.to_string()).collect()
+}
+
+fn process_lines(lines: &[String], moves: &[String]) -> String {
+    /*
+     Given an initial arrangement of crates and a list of movements for them,
+     return the list of crates on top of each stack.
+     
+     See Part 1 of https://adventofcode.com/2022/day/5
+     */
+    top_crates(&simulate(&parse_stacks(lines).unwrap(), &parse_moves(moves).unwrap(), &CrateMover9000).unwrap())
+}
+
+fn process_lines2(lines: &[String], moves: &[String]) -> String {
+    /*
+     This is the same as process_lines(), except when multiple crates are moved
+     in the same step, they're moved together without changing their order.
+     
+     See Part 2 of https://adventofcode.com/2022/day/5
+     */
+    top_crates(&simulate(&parse_stacks(lines).unwrap(), &parse_moves(moves).unwrap(), &CrateMover9001).unwrap())
 }
 
 #[cfg(test)]
@@ -259,6 +749,258 @@ mod tests {
         assert_eq!(process_lines2(&lines, &moves),
     "MCD".to_string());
     }
+
+    #[test]
+    fn test_capped_crane() {
+        let (lines, moves) = read_lines("day05_input_short.txt");
+        let stacks = parse_stacks(&lines).unwrap();
+        let moves = parse_moves(&moves).unwrap();
+        // lifting one crate at a time is the CrateMover 9000
+        assert_eq!(top_crates(&simulate(&stacks, &moves, &CappedCrane::new(1).unwrap()).unwrap()), "CMZ");
+        // lifting everything at once is the CrateMover 9001
+        assert_eq!(top_crates(&simulate(&stacks, &moves, &CappedCrane::new(3).unwrap()).unwrap()), "MCD");
+        let stacks = simulate(&stacks, &moves, &CappedCrane::new(2).unwrap()).unwrap();
+        assert_eq!(stacks, vec![vec!['M'], vec!['C'], vec!['P', 'N', 'D', 'Z']]);
+    }
+
+    #[test]
+    fn test_capped_crane_full() {
+        let (lines, moves) = read_lines("day05_input.txt");
+        let stacks = parse_stacks(&lines).unwrap();
+        let moves = parse_moves(&moves).unwrap();
+        assert_eq!(simulate(&stacks, &moves, &CappedCrane::new(1).unwrap()), simulate(&stacks, &moves, &CrateMover9000));
+        assert_eq!(simulate(&stacks, &moves, &CappedCrane::new(100).unwrap()), simulate(&stacks, &moves, &CrateMover9001));
+    }
+
+    #[test]
+    fn test_capped_crane_zero_capacity() {
+        assert!(matches!(CappedCrane::new(0), Err(MoveError::ZeroCapacity)));
+        assert_eq!(MoveError::ZeroCapacity.to_string(), "a crane has to be able to lift at least one crate");
+    }
+
+    #[test]
+    fn test_dyn_crane() {
+        let (lines, moves) = read_lines("day05_input_short.txt");
+        let stacks = parse_stacks(&lines).unwrap();
+        let moves = parse_moves(&moves).unwrap();
+        let cranes: Vec<Box<dyn Crane<char>>> = vec![
+            Box::new(CrateMover9000), Box::new(CrateMover9001), Box::new(CappedCrane::new(2).unwrap())];
+        let tops: Vec<String> = cranes.iter()
+            .map(|crane| top_crates(&simulate(&stacks, &moves, crane.as_ref()).unwrap()))
+            .collect();
+        assert_eq!(tops, vec!["CMZ", "MCD", "MCZ"]);
+    }
+
+    fn to_lines(drawing: &[&str]) -> Vec<String> {
+        drawing.iter().map(|each| each.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_diagram_ragged() {
+        // the same drawing as the example, with the trailing whitespace trimmed
+        let lines = to_lines(&["    [D]", "[N] [C]", "[Z] [M] [P]", " 1   2   3"]);
+        assert_eq!(parse_stacks(&lines), Ok(vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]));
+        // and with a completely empty stack at the end
+        let lines = to_lines(&["[A]", " 1   2"]);
+        assert_eq!(parse_stacks(&lines), Ok(vec![vec!['A'], vec![]]));
+    }
+
+    #[test]
+    fn test_parse_diagram_wide() {
+        let mut crates: Vec<String> = Vec::new();
+        let mut labels: Vec<String> = Vec::new();
+        for stack in 1..=12 {
+            crates.push(format!("[{}]", char::from_u32('A' as u32 + stack - 1).unwrap()));
+            labels.push(format!("{:^3}", stack));
+        }
+        let lines: Vec<String> = vec![crates.join(" "), labels.join(" ")];
+        let stacks = parse_stacks(&lines).unwrap();
+        assert_eq!(stacks.len(), 12);
+        assert_eq!(stacks[9], vec!['J']);
+        assert_eq!(stacks[11], vec!['L']);
+        assert_eq!(render_stacks(&stacks), lines);
+    }
+
+    #[test]
+    fn test_parse_diagram_multi_character() {
+        let lines = to_lines(&["       [XYZ]", "[AB]  [C]", " 1     2"]);
+        let stacks = parse_diagram(&lines).unwrap();
+        assert_eq!(stacks, vec![vec!["AB".to_string()], vec!["C".to_string(), "XYZ".to_string()]]);
+        assert_eq!(render_stacks(&stacks), to_lines(&["      [XYZ]", "[AB]   [C] ", "  1     2  "]));
+        assert_eq!(parse_diagram(&render_stacks(&stacks)), Ok(stacks));
+        assert_eq!(parse_stacks(&lines), Err(DiagramError::WideCrate { line: 2, stack: 1, label: "AB".to_string() }));
+        let stacks = simulate(&parse_diagram(&lines).unwrap(), &[Move { count: 2, from: 1, to: 0 }], &CrateMover9000).unwrap();
+        assert_eq!(top_crates(&stacks), "C");
+    }
+
+    #[test]
+    fn test_parse_diagram_errors() {
+        assert_eq!(parse_diagram(&[]), Err(DiagramError::Empty));
+        assert_eq!(parse_diagram(&to_lines(&["[A] [B]", "[C] [D]"])), Err(DiagramError::MissingLabels { line: 2 }));
+        assert_eq!(parse_diagram(&to_lines(&["[A] [B]", " 1   3"])),
+            Err(DiagramError::UnexpectedLabel { expected: 2, found: "3".to_string() }));
+        assert_eq!(parse_diagram(&to_lines(&["[A] B", " 1   2"])),
+            Err(DiagramError::UnexpectedCharacter { line: 1, column: 5, found: 'B' }));
+        assert_eq!(parse_diagram(&to_lines(&["[A] [B", " 1   2"])), Err(DiagramError::UnclosedCrate { line: 1, column: 5 }));
+        assert_eq!(parse_diagram(&to_lines(&["[A] []", " 1   2"])), Err(DiagramError::EmptyCrate { line: 1, column: 5 }));
+        assert_eq!(parse_diagram(&to_lines(&["  [A]", " 1   2"])), Err(DiagramError::MisalignedCrate { line: 1, column: 3 }));
+        assert_eq!(parse_diagram(&to_lines(&["[A] [B]", "    [C]", " 1   2"])), Err(DiagramError::FloatingCrate { line: 1, stack: 1 }));
+    }
+
+    #[test]
+    fn test_render_stacks() {
+        let (lines, moves) = read_lines("day05_input_short.txt");
+        let stacks = parse_stacks(&lines).unwrap();
+        assert_eq!(render_stacks(&stacks), lines);
+        let (lines, _moves) = read_lines("day05_input.txt");
+        assert_eq!(render_stacks(&parse_stacks(&lines).unwrap()), lines);
+        let stacks = simulate(&stacks, &parse_moves(&moves).unwrap(), &CrateMover9000).unwrap();
+        assert_eq!(render_stacks(&stacks), to_lines(&["        [Z]", "        [N]", "        [D]",
+            "[C] [M] [P]", " 1   2   3 "]));
+    }
+
+    #[test]
+    fn test_session_step_and_undo() {
+        let (lines, moves) = read_lines("day05_input_short.txt");
+        let mut session = Session::new(parse_stacks(&lines).unwrap(), parse_moves(&moves).unwrap(), CrateMover9000);
+        assert_eq!(top_crates(session.stacks()), "NDP");
+        assert_eq!(session.step(), Ok(Some(Move { count: 1, from: 1, to: 0 })));
+        assert_eq!(top_crates(session.stacks()), "DCP");
+        session.step().unwrap();
+        assert_eq!(top_crates(session.stacks()), "CZ");
+        assert_eq!(session.undo(), Some(Move { count: 3, from: 0, to: 2 }));
+        assert_eq!(top_crates(session.stacks()), "DCP");
+        session.step().unwrap();
+        session.step().unwrap();
+        assert_eq!(top_crates(session.stacks()), "MZ");
+        session.step().unwrap();
+        assert_eq!(top_crates(session.stacks()), "CMZ");
+        assert_eq!(session.position(), 4);
+        assert_eq!(session.step(), Ok(None));
+        for _step in 0..4 {
+            assert!(session.undo().is_some());
+        }
+        assert_eq!(session.undo(), None);
+        assert_eq!(top_crates(session.stacks()), "NDP");
+    }
+
+    #[test]
+    fn test_session_state_at() {
+        let (lines, moves) = read_lines("day05_input_short.txt");
+        let mut session = Session::new(parse_stacks(&lines).unwrap(), parse_moves(&moves).unwrap(), CrateMover9001);
+        assert_eq!(top_crates(session.state_at(2).unwrap()), "CD");
+        assert_eq!(top_crates(session.state_at(4).unwrap()), "MCD");
+        assert_eq!(session.position(), 0);
+        assert_eq!(session.state_at(5), Err(MoveError::NoSuchStep { step: 5, total: 4 }));
+        let trace: Vec<String> = session.trace().unwrap().iter().map(|each| top_crates(each)).collect();
+        assert_eq!(trace, vec!["NDP", "DCP", "CD", "CD", "MCD"]);
+        assert_eq!(session.trace().unwrap()[1], vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']]);
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let (lines, _moves) = read_lines("day05_input_short.txt");
+        let stacks = parse_stacks(&lines).unwrap();
+        let moves = parse_moves(&["move 1 from 3 to 1".to_string(), "move 1 from 3 to 2".to_string()]).unwrap();
+        let error = MoveError::NotEnoughCrates { line: 2, each_move: moves[1], stack: 3, available: 0 };
+        assert_eq!(error.to_string(), "move 2 (move 1 from 3 to 2): stack 3 is empty");
+        assert_eq!(simulate(&stacks, &moves, &CrateMover9000), Err(error));
+        let mut session = Session::new(stacks.clone(), moves.clone(), CrateMover9000);
+        session.step().unwrap();
+        assert!(session.step().is_err());
+        // the failed move didn't change anything
+        assert_eq!(session.position(), 1);
+        assert_eq!(top_crates(session.stacks()), "PD");
+        let moves = parse_moves(&["move 4 from 2 to 1".to_string(), "move 1 from 2 to 4".to_string()]).unwrap();
+        assert_eq!(simulate(&stacks, &moves, &CrateMover9001),
+            Err(MoveError::NotEnoughCrates { line: 1, each_move: moves[0], stack: 2, available: 3 }));
+        assert_eq!(simulate(&stacks, &moves[1..], &CrateMover9001),
+            Err(MoveError::InvalidStack { line: 1, each_move: moves[1], stack: 4 }));
+        assert_eq!(parse_moves(&["move 1 from 0 to 1".to_string()]),
+            Err(MoveError::Malformed { line: 1, text: "move 1 from 0 to 1".to_string() }));
+        assert_eq!(parse_moves(&["".to_string(), "move one from 1 to 2".to_string()]),
+            Err(MoveError::Malformed { line: 1, text: "".to_string() }));
+    }
+
+    fn check_plan(start: &[String], target: &[String], crane: &impl Crane<String>) -> Vec<String> {
+        let plan = plan_from_diagrams(start, target, crane).unwrap();
+        let stacks = simulate(&parse_diagram(start).unwrap(), &parse_moves(&plan).unwrap(), crane).unwrap();
+        assert_eq!(render_stacks(&stacks), target);
+        plan
+    }
+
+    #[test]
+    fn test_plan_moves_short() {
+        let (lines, moves) = read_lines("day05_input_short.txt");
+        let stacks = parse_diagram(&lines).unwrap();
+        let target = render_stacks(&simulate(&stacks, &parse_moves(&moves).unwrap(), &CrateMover9000).unwrap());
+        assert!(check_plan(&lines, &target, &CrateMover9000).len() <= moves.len());
+        check_plan(&lines, &target, &CrateMover9001);
+        check_plan(&target, &lines, &CappedCrane::new(2).unwrap());
+        assert_eq!(plan_from_diagrams(&lines, &lines, &CrateMover9000), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_plan_moves_full() {
+        let (lines, moves) = read_lines("day05_input.txt");
+        let stacks = parse_diagram(&lines).unwrap();
+        let target = render_stacks(&simulate(&stacks, &parse_moves(&moves).unwrap(), &CrateMover9001).unwrap());
+        let plan = check_plan(&lines, &target, &CrateMover9001);
+        assert!(plan.len() < moves.len());
+        check_plan(&target, &lines, &CrateMover9000);
+    }
+
+    #[test]
+    fn test_plan_moves_compresses() {
+        // the CrateMover 9000 can flip a whole stack in one move
+        let start = vec![vec!['A', 'B', 'C'], vec![]];
+        let target = vec![vec![], vec!['C', 'B', 'A']];
+        assert_eq!(plan_moves(&start, &target, &CrateMover9000), Ok(vec![Move { count: 3, from: 0, to: 1 }]));
+        assert!(plan_moves(&start, &target, &CrateMover9001).unwrap().len() > 1);
+    }
+
+    fn write_plan(start: &[String], moves: &[String]) -> String {
+        /*
+        Put a drawing and a list of moves together the same way as the puzzle
+        input, so that read_lines() can read it back in.
+        */
+        let mut result: String = String::new();
+        for each_line in start {
+            result += each_line;
+            result += "\n";
+        }
+        result += "\n";
+        for each_move in moves {
+            result += each_move;
+            result += "\n";
+        }
+        result
+    }
+
+    #[test]
+    fn test_write_plan() {
+        let (lines, moves) = read_lines("day05_input_short.txt");
+        let stacks = parse_diagram(&lines).unwrap();
+        let target = render_stacks(&simulate(&stacks, &parse_moves(&moves).unwrap(), &CrateMover9001).unwrap());
+        let plan = plan_from_diagrams(&lines, &target, &CrateMover9001).unwrap();
+        let filename = std::env::temp_dir().join(format!("day05_plan_{}.txt", std::process::id()));
+        fs::write(&filename, write_plan(&lines, &plan)).unwrap();
+        let (read_back, read_moves) = read_lines(filename.to_str().unwrap());
+        fs::remove_file(&filename).unwrap();
+        assert_eq!((&read_back, &read_moves), (&lines, &plan));
+        assert_eq!(process_lines2(&read_back, &read_moves), "MCD");
+    }
+
+    #[test]
+    fn test_plan_moves_errors() {
+        let start = vec![vec!['A', 'B'], vec![]];
+        assert_eq!(plan_moves(&start, &[vec!['B', 'A'], vec![]], &CrateMover9000), Err(PlanError::NotEnoughStacks));
+        assert_eq!(plan_moves(&start, &[vec!['A'], vec!['C']], &CrateMover9000), Err(PlanError::DifferentCrates));
+        assert_eq!(plan_moves(&start, &[vec!['A', 'B']], &CrateMover9000),
+            Err(PlanError::StackCountMismatch { start: 2, target: 1 }));
+        assert_eq!(plan_from_diagrams(&["[A]".to_string()], &["[A]".to_string()], &CrateMover9000),
+            Err(PlanError::Diagram(DiagramError::MissingLabels { line: 1 })));
+    }
 }
 
 pub fn main() {
@@ -266,5 +1008,42 @@ pub fn main() {
     println!("Day 5:");
     println!("Part 1 - The crates that end up on top of each stack are: {}", process_lines(&lines, &moves));
     println!("Part 2 - The crates that end up on top of each stack are: {}", process_lines2(&lines, &moves));
+    let mut session = Session::new(parse_stacks(&lines).unwrap(), parse_moves(&moves).unwrap(), CrateMover9000);
+    let tallest = |stacks: &[Vec<char>]| stacks.iter().map(|each_stack| each_stack.len()).max().unwrap_or(0);
+    let (mut highest, mut highest_at) = (tallest(session.stacks()), session.position());
+    loop {
+        match session.step() {
+            Ok(Some(_)) if tallest(session.stacks()) > highest =>
+                (highest, highest_at) = (tallest(session.stacks()), session.position()),
+            Ok(Some(_)) => (),
+            Ok(None) => {
+                println!("The tallest stack in Part 1 reaches {} crates after move {}", highest, highest_at);
+                break;
+            },
+            Err(error) => {
+                println!("The moves can't be carried out: {}", error);
+                break;
+            },
+        }
+    }
+    while session.position() > highest_at {
+        session.undo();
+    }
+    println!("At that point the top crates read: {}", top_crates(session.stacks()));
+    if let Ok(trace) = session.trace() {
+        let changes: usize = trace.windows(2).filter(|pair| top_crates(&pair[0]) != top_crates(&pair[1])).count();
+        println!("The top crates change on {} of the {} moves", changes, moves.len());
+    }
+    let target: Vec<String> = render_stacks(session.state_at(moves.len()).unwrap());
+    match plan_from_diagrams(&lines, &target, &CrateMover9000) {
+        Ok(plan) => println!("The same final arrangement only takes {} moves instead of {}", plan.len(), moves.len()),
+        Err(error) => println!("No plan was found for the final arrangement: {}", error),
+    }
+    let stacks: Vec<Vec<char>> = simulate(&parse_stacks(&lines).unwrap(), &parse_moves(&moves).unwrap(), &CappedCrane::new(2).unwrap()).unwrap();
+    println!("With a crane that can only lift two crates at a time, the top crates are: {}", top_crates(&stacks));
+    println!("and the stacks end up looking like this:");
+    for each_line in render_stacks(&stacks) {
+        println!("{}", each_line);
+    }
     println!("");
 }
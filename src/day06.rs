@@ -1,5 +1,7 @@
 use std::fs;
-use std::collections::HashSet;
+use std::io::{self, Read};
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
 
 fn read_lines(filename: &str) -> Vec<String> {
     /*
@@ -15,125 +17,328 @@ fn read_lines(filename: &str) -> Vec<String> {
     lines
 }
 
-fn process_lines(lines: &Vec<String>) -> i32 {
+pub trait Counts<T> {
+    /*
+    Somewhere to keep how many times each symbol appears in the window. Bytes
+    only have 256 values, so a plain array does; characters need a map.
+    */
+    fn empty() -> Self;
+    fn count_mut(&mut self, symbol: T) -> &mut usize;
+}
+
+impl Counts<u8> for [usize; 256] {
+    fn empty() -> [usize; 256] {
+        [0; 256]
+    }
+
+    fn count_mut(&mut self, symbol: u8) -> &mut usize {
+        &mut self[symbol as usize]
+    }
+}
+
+impl<T: Eq + Hash> Counts<T> for HashMap<T, usize> {
+    fn empty() -> HashMap<T, usize> {
+        HashMap::new()
+    }
+
+    fn count_mut(&mut self, symbol: T) -> &mut usize {
+        self.entry(symbol).or_insert(0)
+    }
+}
+
+pub struct MarkerDetector<T, C> {
+    window: usize,
+    // how many times each symbol appears in the current window
+    counts: C,
+    recent: VecDeque<T>,
+    // how many symbols appear more than once in the current window
+    repeated: usize,
+    position: usize,
+}
+
+impl<T: Copy, C: Counts<T>> MarkerDetector<T, C> {
+    pub fn new(window: usize) -> MarkerDetector<T, C> {
+        assert!(window > 0, "the marker window must hold at least one symbol");
+        MarkerDetector { window, counts: C::empty(), recent: VecDeque::with_capacity(window + 1), repeated: 0, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        /*
+        The number of symbols read so far.
+        */
+        self.position
+    }
+
+    pub fn push(&mut self, symbol: T) -> bool {
+        /*
+        Slide the window forward by one symbol, and return whether the last
+        `window` symbols are now all different. Only the symbol coming in and
+        the symbol falling out get looked at, so this is O(1) per symbol
+        instead of rebuilding a set for every window.
+        */
+        self.position += 1;
+        self.recent.push_back(symbol);
+        let count: &mut usize = self.counts.count_mut(symbol);
+        *count += 1;
+        if *count == 2 {
+            self.repeated += 1;
+        }
+        if self.recent.len() > self.window {
+            let oldest: T = self.recent.pop_front().unwrap();
+            let count: &mut usize = self.counts.count_mut(oldest);
+            *count -= 1;
+            if *count == 1 {
+                self.repeated -= 1;
+            }
+        }
+        self.recent.len() == self.window && self.repeated == 0
+    }
+}
+
+fn scan_markers<R: Read>(mut reader: R, window: usize, first_only: bool) -> io::Result<Vec<usize>> {
+    let mut detector: MarkerDetector<u8, [usize; 256]> = MarkerDetector::new(window);
+    let mut markers: Vec<usize> = Vec::new();
+    let mut buffer: [u8; 8192] = [0; 8192];
+    loop {
+        let length: usize = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(markers),
+            Ok(length) => length,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        for byte in &buffer[..length] {
+            if detector.push(*byte) {
+                markers.push(detector.position());
+                if first_only {
+                    return Ok(markers);
+                }
+            }
+        }
+    }
+}
+
+pub fn find_markers<R: Read>(reader: R, window: usize) -> io::Result<Vec<usize>> {
+    /*
+    Read the whole stream and return every offset at which the last `window`
+    bytes were all different. An offset is the number of bytes read up to and
+    including the end of the marker, the same way the puzzle counts them.
+    */
+    scan_markers(reader, window, false)
+}
+
+pub fn first_marker<R: Read>(reader: R, window: usize) -> io::Result<Option<usize>> {
+    /*
+    Like find_markers(), but stop reading as soon as the first marker shows up.
+    */
+    Ok(scan_markers(reader, window, true)?.first().copied())
+}
+
+pub fn char_markers(text: &str, window: usize) -> Vec<usize> {
+    /*
+    The same as find_markers(), but over the characters of a string instead of
+    its bytes, so a multi-byte character like 'é' counts once. Offsets are
+    counted in characters as well.
+    */
+    let mut detector: MarkerDetector<char, HashMap<char, usize>> = MarkerDetector::new(window);
+    let mut markers: Vec<usize> = Vec::new();
+    for each in text.chars() {
+        if detector.push(each) {
+            markers.push(detector.position());
+        }
+    }
+    markers
+}
+
+fn process_lines(lines: &[String]) -> Option<usize> {
     /*
      Calculate how many characters are read until detecting the
-     start-of-packet marker.
+     start-of-packet marker, or None if the datastream doesn't have one.
 
      See Part 1 of https://adventofcode.com/2022/day/6
      */
-    let input = lines[0].clone();
-    let mut result: i32 = 4;
-    for index in 4..input.len() {
-        let rolling_window: HashSet<char> = input[index-4..index].chars().collect();
-        if rolling_window.len() == 4 {
-            result = i32::try_from(index as u32).unwrap();
-            break;
-        }
-    }
-    result
+    char_markers(lines.first()?, 4).first().copied()
 }
 
-fn process_lines2(lines: &Vec<String>) -> i32 {
+fn process_lines2(lines: &[String]) -> Option<usize> {
     /*
      Calculate how many characters are read until detecting the
-     start-of-message marker.
+     start-of-message marker, or None if the datastream doesn't have one.
 
      See Part 2 of https://adventofcode.com/2022/day/6
      */
-    let input = lines[0].clone();
-    let mut result: i32 = 14;
-    for index in 14..input.len() {
-        let rolling_window: HashSet<char> = input[index-14..index].chars().collect();
-        if rolling_window.len() == 14 {
-            result = i32::try_from(index as u32).unwrap();
-            break;
-        }
-    }
-    result
+    char_markers(lines.first()?, 14).first().copied()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::day02_simulator::Rng;
 
     #[test]
     fn test_process_lines_example_01() {
         let lines = read_lines("day06_input_short.txt");
-        assert_eq!(process_lines(&lines), 7);
+        assert_eq!(process_lines(&lines), Some(7));
     }
 
     #[test]
     fn test_process_lines_example_02() {
         let lines = vec!["bvwbjplbgvbhsrlpgdmjqwftvncz".to_string()];
-        assert_eq!(process_lines(&lines), 5);
+        assert_eq!(process_lines(&lines), Some(5));
     }
 
     #[test]
     fn test_process_lines_example_03() {
         let lines = vec!["nppdvjthqldpwncqszvftbrmjlhg".to_string()];
-        assert_eq!(process_lines(&lines), 6);
+        assert_eq!(process_lines(&lines), Some(6));
     }
 
     #[test]
     fn test_process_lines_example_04() {
         let lines = vec!["nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".to_string()];
-        assert_eq!(process_lines(&lines), 10);
+        assert_eq!(process_lines(&lines), Some(10));
     }
 
     #[test]
     fn test_process_lines_example_05() {
         let lines = vec!["zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".to_string()];
-        assert_eq!(process_lines(&lines), 11);
+        assert_eq!(process_lines(&lines), Some(11));
     }
 
     #[test]
     fn test_process_lines_full() {
         let lines = read_lines("day06_input.txt");
-        assert_eq!(process_lines(&lines), 1892);
+        assert_eq!(process_lines(&lines), Some(1892));
     }
 
     #[test]
     fn test_process_lines2_example_01() {
         let lines = read_lines("day06_input_short.txt");
-        assert_eq!(process_lines2(&lines), 19);
+        assert_eq!(process_lines2(&lines), Some(19));
     }
 
     #[test]
     fn test_process_lines2_example_02() {
         let lines = vec!["bvwbjplbgvbhsrlpgdmjqwftvncz".to_string()];
-        assert_eq!(process_lines2(&lines), 23);
+        assert_eq!(process_lines2(&lines), Some(23));
     }
 
     #[test]
     fn test_process_lines2_example_03() {
         let lines = vec!["nppdvjthqldpwncqszvftbrmjlhg".to_string()];
-        assert_eq!(process_lines2(&lines), 23);
+        assert_eq!(process_lines2(&lines), Some(23));
     }
 
     #[test]
     fn test_process_lines2_example_04() {
         let lines = vec!["nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".to_string()];
-        assert_eq!(process_lines2(&lines), 29);
+        assert_eq!(process_lines2(&lines), Some(29));
     }
 
     #[test]
     fn test_process_lines2_example_05() {
         let lines = vec!["zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".to_string()];
-        assert_eq!(process_lines2(&lines), 26);
+        assert_eq!(process_lines2(&lines), Some(26));
     }
 
     #[test]
     fn test_process_lines2_full() {
         let lines = read_lines("day06_input.txt");
-        assert_eq!(process_lines2(&lines), 2313);
+        assert_eq!(process_lines2(&lines), Some(2313));
+    }
+
+    #[test]
+    fn test_find_markers() {
+        // every window of 4 from "abcd" onwards is distinct until the "a" repeats
+        assert_eq!(find_markers("abcdeab".as_bytes(), 4).unwrap(), vec![4, 5, 6, 7]);
+        assert_eq!(find_markers("abcdaaaabcd".as_bytes(), 4).unwrap(), vec![4, 5, 11]);
+        assert_eq!(find_markers("aaaa".as_bytes(), 4).unwrap(), Vec::<usize>::new());
+        assert_eq!(find_markers("aaaa".as_bytes(), 1).unwrap(), vec![1, 2, 3, 4]);
+        // the marker can end on the very last byte
+        assert_eq!(first_marker("aabcd".as_bytes(), 4).unwrap(), Some(5));
+    }
+
+    #[test]
+    fn test_find_markers_stream() {
+        // a reader that hands out at most 3 bytes at a time, so that markers
+        // span several reads
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+                let length: usize = self.0.len().min(3).min(buffer.len());
+                buffer[..length].copy_from_slice(&self.0[..length]);
+                self.0 = &self.0[length..];
+                Ok(length)
+            }
+        }
+        let contents = fs::read("day06_input.txt").unwrap();
+        assert_eq!(find_markers(Trickle(&contents), 14).unwrap(), find_markers(&contents[..], 14).unwrap());
+        assert_eq!(first_marker(Trickle(&contents), 14).unwrap(), Some(2313));
+        assert_eq!(first_marker(fs::File::open("day06_input.txt").unwrap(), 4).unwrap(), Some(1892));
+    }
+
+    #[test]
+    fn test_no_marker() {
+        let lines = vec!["abcabcabc".to_string()];
+        assert_eq!(process_lines(&lines), None);
+        assert_eq!(process_lines2(&read_lines("day06_input_short.txt")[..0]), None);
+        assert_eq!(process_lines(&["".to_string()]), None);
+        assert_eq!(first_marker("abc".as_bytes(), 4).unwrap(), None);
+    }
+
+    #[test]
+    fn test_multi_byte_characters() {
+        // 'é' is two bytes, but only one character
+        let lines = vec!["ééaébcd".to_string()];
+        assert_eq!(process_lines(&lines), Some(6));
+        assert_eq!(char_markers("日本日本語", 3), vec![5]);
+        // as bytes, "é" followed by "a" is already three different bytes
+        assert_eq!(first_marker("éa".as_bytes(), 3).unwrap(), Some(3));
+    }
+
+    fn brute_force<T: Eq + std::hash::Hash + Copy>(symbols: &[T], window: usize) -> Vec<usize> {
+        (window..=symbols.len())
+            .filter(|end| symbols[end - window..*end].iter().collect::<std::collections::HashSet<&T>>().len() == window)
+            .collect()
+    }
+
+    #[test]
+    fn test_markers_against_brute_force() {
+        let alphabet: Vec<char> = "abcdeé日ß".chars().collect();
+        let mut rng: Rng = Rng::new(2022);
+        for _round in 0..2000 {
+            let window: usize = rng.next_below(6) as usize + 1;
+            let length: usize = rng.next_below(40) as usize;
+            // a smaller alphabet makes repeats, and so missing markers, more likely
+            let letters: u64 = rng.next_below(alphabet.len() as u64) + 1;
+            let text: String = (0..length).map(|_| alphabet[rng.next_below(letters) as usize]).collect();
+            let characters: Vec<char> = text.chars().collect();
+            assert_eq!(char_markers(&text, window), brute_force(&characters, window), "{:?} with a window of {}", text, window);
+            assert_eq!(find_markers(text.as_bytes(), window).unwrap(), brute_force(text.as_bytes(), window),
+                "{:?} with a window of {}", text, window);
+            assert_eq!(first_marker(text.as_bytes(), window).unwrap(), brute_force(text.as_bytes(), window).first().copied());
+        }
     }
 }
 
 pub fn main() {
     let result = read_lines("day06_input.txt");
     println!("Day 6:");
-    println!("Part 1 - The number of characters processed before the start-of-packet marker is: {}", process_lines(&result));
-    println!("Part 2 - The number of characters processed before the start-of-message marker is: {}", process_lines2(&result));
+    match process_lines(&result) {
+        Some(marker) => println!("Part 1 - The number of characters processed before the start-of-packet marker is: {}", marker),
+        None => println!("Part 1 - The datastream doesn't have a start-of-packet marker"),
+    }
+    match process_lines2(&result) {
+        Some(marker) => println!("Part 2 - The number of characters processed before the start-of-message marker is: {}", marker),
+        None => println!("Part 2 - The datastream doesn't have a start-of-message marker"),
+    }
+    match first_marker(fs::File::open("day06_input.txt").unwrap(), 14) {
+        Ok(Some(marker)) => println!("Read as a byte stream, the first start-of-message marker ends at byte {}", marker),
+        Ok(None) => println!("Read as a byte stream, there's no start-of-message marker"),
+        Err(error) => println!("Couldn't read the datastream: {}", error),
+    }
+    match find_markers(fs::File::open("day06_input.txt").unwrap(), 14) {
+        Ok(markers) => println!("The whole datastream has {} places where a start-of-message marker ends", markers.len()),
+        Err(error) => println!("Couldn't read the datastream: {}", error),
+    }
     println!("");
 }
\ No newline at end of file
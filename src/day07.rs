@@ -1,13 +1,17 @@
+use std::env;
 use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::fmt;
+use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet};
 use regex::Regex;
 
 #[derive(Debug)]
 pub struct FileOrDirectory {
     name: String,
-    parent: String,
-    depth: i32,
-    size: i32,
+    size: u64,
 }
 
 fn read_lines(filename: &str) -> Vec<String> {
@@ -27,17 +31,14 @@ fn read_lines(filename: &str) -> Vec<String> {
 fn go_up_one_level(current_directory: &str) -> String {
     /*
     Takes a string representing a certain path, returns the path above it.
-    For example, given "/usr/bin" it returns "/usr/" 
+    For example, given "/usr/bin/" it returns "/usr/". The root has nothing
+    above it, so going up from "/" stays at "/".
     */
-    let result: String;
-    let one_deep: Regex = Regex::new(r"^/\w+/$").unwrap();
-    if one_deep.is_match(&current_directory) {
-        result = "/".to_string();
-    }else {
-        let get_parent = Regex::new(r"^(/\w+/(\w+/)*)\w+/$").unwrap();
-        result = get_parent.captures(current_directory).unwrap().get(1).unwrap().as_str().to_string()
+    let trimmed: &str = current_directory.strip_suffix('/').unwrap_or(current_directory);
+    match trimmed.rfind('/') {
+        Some(index) => trimmed[..index+1].to_string(),
+        None => "/".to_string(),
     }
-    result
 }
 
 fn go_down_one_level(current_directory: &str, sub_directory: &str) -> String {
@@ -60,211 +61,689 @@ fn construct_full_directory_path(current_directory: &str, filename: &str) -> Str
     result.to_string()
 }
 
-fn parse_input(lines: &Vec<String>) -> (Vec<FileOrDirectory>, Vec<FileOrDirectory>) {
-    let cd_command = Regex::new(r"^\$\s+cd\s+(/|\.\.|\w+)").unwrap();
-    let ls_command = Regex::new(r"^\$\s+ls").unwrap();
-    let directory_line = Regex::new(r"^dir\s+(\w+)").unwrap();
-    let file_line = Regex::new(r"^(\d+)\s+(.+)").unwrap();
+fn change_directory(current_directory: &str, target: &str) -> String {
+    /*
+    Work out where "cd target" ends up. The target can be an absolute path
+    like "/a/b", or a relative one like "b", "../c" or "b/./d". Names can be
+    anything except "/", so "my-dir", "a.b" and "two words" are all fine.
+    */
+    let mut result: String = if target.starts_with('/') {
+        "/".to_string()
+    }else {
+        current_directory.to_string()
+    };
+    for each in target.split('/') {
+        match each {
+            "" | "." => {},
+            ".." => result = go_up_one_level(&result),
+            _ => result = go_down_one_level(&result, each),
+        }
+    }
+    result
+}
+
+fn parse_input(lines: &[String]) -> (Vec<FileOrDirectory>, Vec<FileOrDirectory>) {
+    let cd_command = Regex::new(r"^\$\s+cd\s+(.+)$").unwrap();
+    let ls_command = Regex::new(r"^\$\s+ls\s*$").unwrap();
+    let directory_line = Regex::new(r"^dir\s+(.+)$").unwrap();
+    let file_line = Regex::new(r"^(\d+)\s+(.+)$").unwrap();
     let mut directories: Vec<FileOrDirectory> = Vec::new();
     let mut files: Vec<FileOrDirectory> = Vec::new();
+    // everything listed so far, so that running ls twice in the same
+    // directory doesn't count anything twice
+    let mut seen: HashSet<String> = HashSet::from(["/".to_string()]);
     let mut current_directory = "/".to_string();
-    let mut current_depth = 0;
     let mut index: usize = 0;
     // don't forget to initialize "/"!
     directories.push(FileOrDirectory{
-        parent: "/".to_string(),
-        depth: -1,
         size: 0,
         name: "/".to_string(),
     });
     // process the command history
-    while index < lines.len()-1 {
-        if ls_command.is_match(&lines[index].as_str()) {
+    while index < lines.len() {
+        if ls_command.is_match(lines[index].as_str()) {
             index += 1;
             // the next unknown many lines are files or directories inside of current_directory
-            loop {
-                if index > lines.len()-1 {
-                    break;
-                }
-                if directory_line.is_match(&lines[index].as_str()) {
-                    let directory = FileOrDirectory{
-                        parent: current_directory.clone(),
-                        depth: current_depth,
-                        size: 0,
-                        name: construct_full_directory_path(&current_directory, &directory_line.captures(&lines[index]).unwrap().get(1).unwrap().as_str()),
-                    };
-                    directories.push(directory);
-                }else if file_line.is_match(&lines[index].as_str()) {
-                    let file = FileOrDirectory{
-                        parent: current_directory.clone(),
-                        depth: current_depth,
-                        size: i32::from_str(file_line.captures(&lines[index]).unwrap().get(1).unwrap().as_str()).unwrap(),
-                        name: construct_full_path(&current_directory, &file_line.captures(&lines[index]).unwrap().get(2).unwrap().as_str()),
-                    };
-                    files.push(file);
+            while index < lines.len() {
+                if let Some(captures) = directory_line.captures(&lines[index]) {
+                    let name: String = construct_full_directory_path(&current_directory, captures.get(1).unwrap().as_str());
+                    if seen.insert(name.clone()) {
+                        directories.push(FileOrDirectory{
+                            size: 0,
+                            name,
+                        });
+                    }
+                }else if let Some((captures, size)) = file_line.captures(&lines[index])
+                        .and_then(|captures| u64::from_str(captures.get(1).unwrap().as_str()).ok().map(|size| (captures, size))) {
+                    // a size too big for a u64 falls through to "Unrecognized input"
+                    let name: String = construct_full_path(&current_directory, captures.get(2).unwrap().as_str());
+                    if seen.insert(name.clone()) {
+                        files.push(FileOrDirectory{
+                            size,
+                            name,
+                        });
+                    }
                 }else {
                     break;
                 }
                 index += 1;
             }
-        }else if cd_command.is_match(&lines[index].as_str()) {
-            match cd_command.captures(&lines[index]).unwrap().get(1).unwrap().as_str() {
-                "/" => {
-                    current_directory = "/".to_string().clone();
-                    current_depth = 0;
-                },
-                ".." => {
-                    let new_directory = go_up_one_level(&current_directory);
-                    current_directory = new_directory.clone();
-                    current_depth -= 1;
-                },
-                _ => {
-                    let new_directory = go_down_one_level(&current_directory, &cd_command.captures(&lines[index]).unwrap().get(1).unwrap().as_str());
-                    current_directory = new_directory.clone();
-                    current_depth += 1;       
-                }
-            }
+        }else if let Some(captures) = cd_command.captures(&lines[index]) {
+            current_directory = change_directory(&current_directory, captures.get(1).unwrap().as_str());
             index += 1;
         }else {
             println!("Unrecognized input {} on line {}", &lines[index], index);
+            index += 1;
         }
     }
     (directories, files)
 }
 
-fn process_lines(lines: &Vec<String>) -> i32 {
-    /*
-     Takes the command-line history of a device, determines what directories
-     had AT MOST 100k bytes, then sums up the total size of those directories.
+#[derive(Debug)]
+pub struct Node {
+    // the last part of the path, or "/" for the root
+    pub name: String,
+    // the full path, with a trailing "/" for directories
+    pub path: String,
+    pub is_directory: bool,
+    // for directories, the total size of everything inside them
+    pub size: u64,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
 
-     See Part 1 of https://adventofcode.com/2022/day/7
-     */
-    // parse the input into directories and files
-    println!("Parsing input.");
-    let (mut directories, mut files) = parse_input(&lines);
-    println!("Done parsing input.");
-
-    // calculate the sizes of all of the subdirectories, starting from the 
-    // lowest levels up
-    files.sort_by_key(|x| x.depth);
-    files.reverse();
-    for each_file in files {
-        // apply file sizes to directory_sizes
-        let mut directory_index = 0;
-        while directory_index < directories.len() {
-            if directories[directory_index].name == each_file.parent {
-                break;
-            }
-            directory_index += 1;
+pub struct FileSystem {
+    // every file and directory, with the root at index 0. Nodes refer to each
+    // other by their index in here.
+    nodes: Vec<Node>,
+    by_path: HashMap<String, usize>,
+}
+
+impl FileSystem {
+    pub fn new() -> FileSystem {
+        let root: Node = Node {
+            name: "/".to_string(),
+            path: "/".to_string(),
+            is_directory: true,
+            size: 0,
+            parent: None,
+            children: Vec::new(),
+        };
+        FileSystem { nodes: vec![root], by_path: HashMap::from([("/".to_string(), 0)]) }
+    }
+
+    pub fn from_entries(directories: &[FileOrDirectory], files: &[FileOrDirectory]) -> Result<FileSystem, SizeOverflow> {
+        /*
+        Build the tree out of the flat lists from parse_input(), then work out
+        the directory sizes.
+        */
+        let mut file_system: FileSystem = FileSystem::new();
+        for each_directory in directories {
+            file_system.add_directory(&each_directory.name);
         }
-        if directory_index >= directories.len() {
-            eprintln!("Couldn't find directory matching the name {}", each_file.parent);
+        for each_file in files {
+            file_system.add_file(&each_file.name, each_file.size);
         }
-        directories[directory_index].size += each_file.size;
+        file_system.compute_sizes()?;
+        Ok(file_system)
     }
 
-    // add the sizes of child directories to parent directories
-    directories.sort_by_key(|x| x.depth);
-    directories.reverse();
-    let mut each_directory_index = 0;
-    while each_directory_index < directories.len() {
-        // stop at root level
-        if directories[each_directory_index].depth == -1 {
-            break;
+    pub fn from_transcript(lines: &[String]) -> Result<FileSystem, SizeOverflow> {
+        let (directories, files) = parse_input(lines);
+        FileSystem::from_entries(&directories, &files)
+    }
+
+    fn split_path(path: &str) -> (&str, &str) {
+        /*
+        Split "/a/b/" or "/a/b" into its parent directory "/a/" and name "b".
+        */
+        let trimmed: &str = path.strip_suffix('/').unwrap_or(path);
+        let split_at: usize = trimmed.rfind('/').map_or(0, |index| index + 1);
+        (&trimmed[..split_at], &trimmed[split_at..])
+    }
+
+    fn add_node(&mut self, path: &str, is_directory: bool, size: u64) -> usize {
+        /*
+        Add a node under its parent directory, creating any missing parent
+        directories along the way. Adding a path that already exists just
+        returns the existing node. Paths are taken from the root, so "a/b"
+        is the same as "/a/b".
+        */
+        if !path.starts_with('/') {
+            return self.add_node(&format!("/{}", path), is_directory, size);
         }
-        // add this directory's size to its parent directory
-        let mut directory_index = 0;
-        while directory_index < directories.len() {
-            if directories[directory_index].name == directories[each_directory_index].parent {
-                break;
+        if let Some(index) = self.by_path.get(path) {
+            return *index;
+        }
+        let (parent_path, name) = FileSystem::split_path(path);
+        let parent: usize = self.add_node(parent_path, true, 0);
+        let index: usize = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            path: path.to_string(),
+            is_directory,
+            size,
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.nodes[parent].children.push(index);
+        self.by_path.insert(path.to_string(), index);
+        index
+    }
+
+    pub fn add_directory(&mut self, path: &str) -> usize {
+        self.add_node(path, true, 0)
+    }
+
+    pub fn add_file(&mut self, path: &str, size: u64) -> usize {
+        self.add_node(path, false, size)
+    }
+
+    pub fn compute_sizes(&mut self) -> Result<(), SizeOverflow> {
+        /*
+        Work out the size of every directory in a single post-order walk, so
+        each directory is only added up after all of its children. Fails if a
+        directory adds up to more than a u64 can hold, rather than wrapping.
+        */
+        let mut stack: Vec<(usize, bool)> = vec![(0, false)];
+        while let Some((index, children_done)) = stack.pop() {
+            if !self.nodes[index].is_directory {
+                continue;
+            }
+            if children_done {
+                self.nodes[index].size = self.nodes[index].children.iter()
+                    .try_fold(0_u64, |total, child| total.checked_add(self.nodes[*child].size))
+                    .ok_or_else(|| SizeOverflow { path: self.nodes[index].path.clone() })?;
+            }else {
+                stack.push((index, true));
+                for child in &self.nodes[index].children {
+                    stack.push((*child, false));
+                }
             }
-            directory_index += 1;
         }
-        if directory_index >= directories.len() {
-            eprintln!("Couldn't find directory matching the name {}", directories[each_directory_index].parent);
+        Ok(())
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.nodes[0]
+    }
+
+    pub fn node(&self, index: usize) -> &Node {
+        &self.nodes[index]
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Node> {
+        self.by_path.get(path).map(|index| &self.nodes[*index])
+    }
+
+    pub fn directories(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|each| each.is_directory)
+    }
+
+    pub fn render_tree(&self) -> Vec<String> {
+        /*
+        Draw the tree the way the puzzle does, with every directory's total
+        size added, for example:
+            - / (dir, size=48381165)
+              - a (dir, size=94853)
+                - e (dir, size=584)
+                  - i (file, size=584)
+        Entries are sorted by name, like the tree command does.
+        */
+        let mut result: Vec<String> = Vec::new();
+        let mut stack: Vec<(usize, usize)> = vec![(0, 0)];
+        while let Some((index, depth)) = stack.pop() {
+            let node: &Node = &self.nodes[index];
+            let kind: &str = if node.is_directory { "dir" } else { "file" };
+            result.push(format!("{}- {} ({}, size={})", "  ".repeat(depth), node.name, kind, node.size));
+            for child in self.sorted_children(index).iter().rev() {
+                stack.push((*child, depth + 1));
+            }
         }
-        directories[directory_index].size += directories[each_directory_index].size;
-        each_directory_index += 1;
+        result
+    }
+
+    pub fn disk_usage(&self) -> Vec<String> {
+        /*
+        List every directory with its human-readable size, largest first, like
+        "du -h | sort -rh" would.
+        */
+        self.largest_directories(usize::MAX).iter()
+            .map(|each| format!("{}\t{}", human_size(each.size), each.path))
+            .collect()
+    }
+
+    pub fn largest_directories(&self, count: usize) -> Vec<&Node> {
+        /*
+        The `count` largest directories, largest first. Directories of the
+        same size are ordered by path.
+        */
+        let mut result: Vec<&Node> = self.directories().collect();
+        result.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        result.truncate(count);
+        result
     }
 
-    // sum the size of the directories that are 100kB or less
-    let mut total_of_small_directories: i32 = 0;
-    directories.sort_by_key(|x| x.size);
-    directories.reverse();
-    for each_directory in directories {
-        if each_directory.size <= 100000 {
-            total_of_small_directories += each_directory.size;
+    fn sorted_children(&self, index: usize) -> Vec<usize> {
+        let mut result: Vec<usize> = self.nodes[index].children.clone();
+        result.sort_by(|a, b| self.nodes[*a].name.cmp(&self.nodes[*b].name));
+        result
+    }
+
+    pub fn to_json(&self) -> String {
+        /*
+        Export the whole tree as JSON. Every entry has a name, type and size,
+        and directories also have a list of children.
+        */
+        let mut result: String = String::new();
+        self.write_json(0, &mut result);
+        result
+    }
+
+    fn write_json(&self, index: usize, result: &mut String) {
+        let node: &Node = &self.nodes[index];
+        let kind: &str = if node.is_directory { "dir" } else { "file" };
+        result.push_str(&format!("{{\"name\":{},\"type\":\"{}\",\"size\":{}", json_string(&node.name), kind, node.size));
+        if node.is_directory {
+            result.push_str(",\"children\":[");
+            for (position, child) in self.sorted_children(index).iter().enumerate() {
+                if position > 0 {
+                    result.push(',');
+                }
+                self.write_json(*child, result);
+            }
+            result.push(']');
         }
+        result.push('}');
     }
-    total_of_small_directories
 }
 
-fn process_lines2(lines: &Vec<String>) -> i32 {
+fn human_size(size: u64) -> String {
     /*
-     Takes the command-line history of a device, determines the recursive 
-     sizes of all directories, then determines the smallest directory to delete
-     such that / will be no larger than 30_000_000 bytes.
+    Format a size the way "du -h" does: plain bytes below 1K, otherwise
+    rounded up to one decimal place below 10 and to a whole number above.
+    */
+    let units: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    if size < 1024 {
+        return size.to_string();
+    }
+    let mut value: f64 = size as f64 / 1024.0;
+    let mut unit: usize = 0;
+    loop {
+        let rounded: f64 = if value < 10.0 { (value * 10.0).ceil() / 10.0 } else { value.ceil() };
+        if rounded < 1024.0 || unit == units.len() - 1 {
+            return if rounded < 10.0 {
+                format!("{:.1}{}", rounded, units[unit])
+            }else {
+                format!("{}{}", rounded, units[unit])
+            };
+        }
+        value /= 1024.0;
+        unit += 1;
+    }
+}
 
-     See Part 2 of https://adventofcode.com/2022/day/7
-     */
-    // parse the input into directories and files
-    println!("Parsing input.");
-    let (mut directories, mut files) = parse_input(&lines);
-    println!("Done parsing input.");
-
-    // calculate the sizes of all of the subdirectories, starting from the 
-    // lowest levels up
-    files.sort_by_key(|x| x.depth);
-    files.reverse();
-    for each_file in files {
-        // apply file sizes to directory_sizes
-        let mut directory_index = 0;
-        while directory_index < directories.len() {
-            if directories[directory_index].name == each_file.parent {
-                break;
+fn json_string(text: &str) -> String {
+    let mut result: String = String::from("\"");
+    for each in text.chars() {
+        match each {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+impl Default for FileSystem {
+    fn default() -> FileSystem {
+        FileSystem::new()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SizeOverflow {
+    // the directory whose total size didn't fit
+    pub path: String,
+}
+
+impl fmt::Display for SizeOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the total size of {} is too big to count", self.path)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DeletionError {
+    DiskTooSmall { disk_size: u64, required_free: u64 },
+}
+
+impl fmt::Display for DeletionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeletionError::DiskTooSmall { disk_size, required_free } =>
+                write!(f, "a disk of {} bytes can never have {} bytes free", disk_size, required_free),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct DeletionPlan {
+    // how many bytes have to be deleted, zero if there's already enough space
+    pub needed: u64,
+    // the smallest directory that frees up enough space on its own
+    pub smallest_directory: Option<(String, u64)>,
+    // the directories, none inside another, whose total size is the smallest
+    // that frees up enough space, along with that total
+    pub best_directories: Option<(Vec<String>, u64)>,
+}
+
+impl FileSystem {
+    pub fn plan_deletion(&self, disk_size: u64, required_free: u64) -> Result<DeletionPlan, DeletionError> {
+        /*
+        Work out what to delete so that the disk has at least `required_free`
+        bytes free. There are two answers: the smallest single directory that
+        is big enough (Part 2 of the puzzle), and the set of directories that
+        gets closest to what's needed without going under. The directories in
+        the set never contain each other, since deleting a directory already
+        deletes everything inside it.
+        */
+        if required_free > disk_size {
+            return Err(DeletionError::DiskTooSmall { disk_size, required_free });
+        }
+        // the used space can be more than the disk size if the transcript is
+        // wrong, so this is worked out in u128 to keep it from overflowing
+        let needed: u64 = (required_free as u128 + self.root().size as u128).saturating_sub(disk_size as u128) as u64;
+        if needed == 0 {
+            return Ok(DeletionPlan { needed, smallest_directory: None, best_directories: Some((Vec::new(), 0)) });
+        }
+        let smallest_directory: Option<(String, u64)> = self.directories()
+            .filter(|each| each.size >= needed)
+            .min_by_key(|each| each.size)
+            .map(|each| (each.path.clone(), each.size));
+        let best_directories: Option<(Vec<String>, u64)> = self.best_deletion_set(needed).map(|(chosen, total)| {
+            let mut paths: Vec<String> = chosen.iter().map(|each| self.nodes[*each].path.clone()).collect();
+            paths.sort();
+            (paths, total)
+        });
+        Ok(DeletionPlan { needed, smallest_directory, best_directories })
+    }
+
+    fn preorder_directories(&self, index: usize, order: &mut Vec<usize>, ends: &mut Vec<usize>) {
+        /*
+        List the directories depth first, along with the position in the list
+        just after each directory's last subdirectory.
+        */
+        let position: usize = order.len();
+        order.push(index);
+        ends.push(0);
+        for child in &self.nodes[index].children {
+            if self.nodes[*child].is_directory {
+                self.preorder_directories(*child, order, ends);
             }
-            directory_index += 1;
         }
-        if directory_index >= directories.len() {
-            eprintln!("Couldn't find directory matching the name {}", each_file.parent);
+        ends[position] = order.len();
+    }
+
+    fn best_deletion_set(&self, needed: u64) -> Option<(Vec<usize>, u64)> {
+        /*
+        A knapsack over the tree. Going through the directories depth first,
+        each one is either skipped, or deleted along with everything inside
+        it, in which case the search carries on after its last subdirectory.
+
+        `reached` holds every total below `needed` that can be freed by the
+        directories seen so far. Deleting a directory adds its size to the
+        totals reachable before it, but those only become reachable once the
+        search has passed everything inside it, so the totals are saved when
+        each directory is reached. The saved totals are also what's used to
+        retrace which directories made up the best total.
+
+        The totals are kept as runs of consecutive totals rather than one
+        entry (or one bit) per byte, so the work depends on the number of
+        directories and how the totals they make up are spread out, not on
+        how many bytes have to be freed.
+        */
+        let size = |position: usize| self.nodes[position].size;
+        let mut order: Vec<usize> = Vec::new();
+        let mut ends: Vec<usize> = Vec::new();
+        self.preorder_directories(0, &mut order, &mut ends);
+        // (first total, last total) of each run, in order
+        let mut reached: Vec<(u64, u64)> = vec![(0, 0)];
+        // the runs of totals when each directory was reached
+        let mut before: Vec<Vec<(u64, u64)>> = Vec::with_capacity(order.len());
+        // the directories that are still open, innermost last
+        let mut open: Vec<usize> = Vec::new();
+        // (total, position of the last directory deleted, total before it)
+        let mut best: Option<(u64, usize, u64)> = None;
+        for position in 0..=order.len() {
+            while open.last().is_some_and(|deleted| ends[*deleted] == position) {
+                let deleted: usize = open.pop().unwrap();
+                let deleted_size: u64 = size(order[deleted]);
+                // anything from `enough` up frees up enough space along with
+                // this directory, and the runs are sorted, so the first such
+                // total is also the smallest
+                let enough: u64 = needed.saturating_sub(deleted_size);
+                let smallest: Option<u64> = before[deleted].iter()
+                    .find(|(_, last)| *last >= enough)
+                    .map(|(first, _)| max(*first, enough));
+                if let Some(total) = smallest {
+                    let freed: u64 = total + deleted_size;
+                    if best.is_none_or(|(best_total, _, _)| freed < best_total) {
+                        best = Some((freed, deleted, total));
+                    }
+                }
+                let added: Vec<(u64, u64)> = before[deleted].iter()
+                    .take_while(|(first, _)| *first < enough)
+                    .map(|(first, last)| (first + deleted_size, min(*last, enough - 1) + deleted_size))
+                    .collect();
+                reached = merge_runs(&reached, &added);
+            }
+            if position < order.len() {
+                before.push(reached.clone());
+                open.push(position);
+            }
+        }
+        let contains = |runs: &[(u64, u64)], total: u64| {
+            runs.get(runs.partition_point(|(_, last)| *last < total)).is_some_and(|(first, _)| *first <= total)
+        };
+        // retrace the directories that were deleted to get the best total.
+        // Whatever made up a total was closed before the directory it was
+        // saved for, and the last of those directories to be reached has the
+        // rest of the total saved for it in turn.
+        let (best_total, mut deleted, mut total) = best?;
+        let mut chosen: Vec<usize> = vec![order[deleted]];
+        while total > 0 {
+            deleted = (0..deleted)
+                .rev()
+                .find(|each| {
+                    ends[*each] <= deleted && size(order[*each]) <= total
+                        && contains(&before[*each], total - size(order[*each]))
+                })
+                .unwrap();
+            chosen.push(order[deleted]);
+            total -= size(order[deleted]);
         }
-        directories[directory_index].size += each_file.size;
+        Some((chosen, best_total))
     }
+}
 
-    // add the sizes of child directories to parent directories
-    directories.sort_by_key(|x| x.depth);
-    directories.reverse();
-    let mut each_directory_index = 0;
-    while each_directory_index < directories.len() {
-        // stop at root level
-        if directories[each_directory_index].depth == -1 {
-            break;
+fn merge_runs(first_runs: &[(u64, u64)], second_runs: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    /*
+    Merge two sorted lists of runs of consecutive totals, joining any runs that
+    overlap or touch.
+    */
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(first_runs.len() + second_runs.len());
+    let (mut first_index, mut second_index) = (0, 0);
+    while first_index < first_runs.len() || second_index < second_runs.len() {
+        let run: (u64, u64) = if second_index == second_runs.len()
+            || (first_index < first_runs.len() && first_runs[first_index].0 <= second_runs[second_index].0) {
+            first_index += 1;
+            first_runs[first_index - 1]
+        } else {
+            second_index += 1;
+            second_runs[second_index - 1]
+        };
+        match merged.last_mut() {
+            Some(last) if run.0 <= last.1 + 1 => last.1 = max(last.1, run.1),
+            _ => merged.push(run),
         }
-        // add this directory's size to its parent directory
-        let mut directory_index = 0;
-        while directory_index < directories.len() {
-            if directories[directory_index].name == directories[each_directory_index].parent {
-                break;
+    }
+    merged
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Difference {
+    // on disk, but not in the transcript
+    Missing { path: String },
+    // in the transcript, but not on disk
+    Unexpected { path: String },
+    Size { path: String, disk: u64, transcript: u64 },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Missing { path } =>
+                write!(f, "{} is on disk but not in the transcript", path),
+            Difference::Unexpected { path } =>
+                write!(f, "{} is in the transcript but not on disk", path),
+            Difference::Size { path, disk, transcript } =>
+                write!(f, "{} is {} bytes on disk but {} bytes in the transcript", path, disk, transcript),
+        }
+    }
+}
+
+impl FileSystem {
+    pub fn from_directory(root: &Path) -> io::Result<FileSystem> {
+        /*
+        Walk a real directory and build the same tree a transcript of it would.
+        Symbolic links are skipped, so a link back up the tree can't send the
+        walk round in circles.
+        */
+        let mut file_system: FileSystem = FileSystem::new();
+        let mut to_visit: Vec<(PathBuf, String)> = vec![(root.to_path_buf(), "/".to_string())];
+        while let Some((directory, path)) = to_visit.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let entry: fs::DirEntry = entry?;
+                let file_type: fs::FileType = entry.file_type()?;
+                let name: String = entry.file_name().to_string_lossy().to_string();
+                if file_type.is_dir() {
+                    let child_path: String = construct_full_directory_path(&path, &name);
+                    file_system.add_directory(&child_path);
+                    to_visit.push((entry.path(), child_path));
+                }else if file_type.is_file() {
+                    file_system.add_file(&construct_full_path(&path, &name), entry.metadata()?.len());
+                }
             }
-            directory_index += 1;
         }
-        if directory_index >= directories.len() {
-            eprintln!("Couldn't find directory matching the name {}", directories[each_directory_index].parent);
+        file_system.compute_sizes()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        Ok(file_system)
+    }
+
+    pub fn to_transcript(&self) -> Vec<String> {
+        /*
+        Write out the commands that would discover this tree: an ls in every
+        directory, visiting the subdirectories in order by name.
+        */
+        let mut result: Vec<String> = vec!["$ cd /".to_string()];
+        self.write_transcript(0, &mut result);
+        result
+    }
+
+    fn write_transcript(&self, index: usize, result: &mut Vec<String>) {
+        let children: Vec<usize> = self.sorted_children(index);
+        result.push("$ ls".to_string());
+        for child in &children {
+            let node: &Node = &self.nodes[*child];
+            if node.is_directory {
+                result.push(format!("dir {}", node.name));
+            }else {
+                result.push(format!("{} {}", node.size, node.name));
+            }
+        }
+        for child in children.iter().filter(|each| self.nodes[**each].is_directory) {
+            result.push(format!("$ cd {}", self.nodes[*child].name));
+            self.write_transcript(*child, result);
+            result.push("$ cd ..".to_string());
         }
-        directories[directory_index].size += directories[each_directory_index].size;
-        each_directory_index += 1;
     }
+}
 
-    // sum the size of the directories that are 100kB or less
-    directories.sort_by_key(|x| x.size);
-    let mut smallest_suitable_size: i32 = 30000000 - (70000000 - directories[directories.len()-1].size);
-    for each_directory in directories {
-        if each_directory.size >= smallest_suitable_size {
-            smallest_suitable_size = each_directory.size;
-            break;
+pub fn transcript_from_directory(root: &Path) -> io::Result<Vec<String>> {
+    Ok(FileSystem::from_directory(root)?.to_transcript())
+}
+
+pub fn diff_against_directory(lines: &[String], root: &Path) -> io::Result<Vec<Difference>> {
+    /*
+    Compare a transcript with the directory it's supposed to describe. Files
+    are compared with their sizes from the parsed transcript, and directories
+    with the sizes reconstructed from it. The differences come back sorted by
+    path.
+    */
+    let disk: FileSystem = FileSystem::from_directory(root)?;
+    let (directories, files) = parse_input(lines);
+    let transcript: FileSystem = FileSystem::from_entries(&directories, &files)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+    let mut transcript_sizes: HashMap<&str, u64> = files.iter()
+        .map(|each| (each.name.as_str(), each.size))
+        .collect();
+    for each in transcript.directories() {
+        transcript_sizes.insert(each.path.as_str(), each.size);
+    }
+    let mut result: Vec<Difference> = Vec::new();
+    for each in &disk.nodes {
+        match transcript_sizes.get(each.path.as_str()) {
+            None => result.push(Difference::Missing { path: each.path.clone() }),
+            Some(size) if *size != each.size =>
+                result.push(Difference::Size { path: each.path.clone(), disk: each.size, transcript: *size }),
+            _ => {},
+        }
+    }
+    for path in transcript_sizes.keys() {
+        if disk.get(path).is_none() {
+            result.push(Difference::Unexpected { path: path.to_string() });
         }
     }
-    smallest_suitable_size
+    result.sort_by(|a, b| difference_path(a).cmp(difference_path(b)));
+    Ok(result)
+}
+
+fn difference_path(difference: &Difference) -> &str {
+    match difference {
+        Difference::Missing { path } | Difference::Unexpected { path } | Difference::Size { path, .. } => path,
+    }
+}
+
+fn process_lines(lines: &[String]) -> u64 {
+    /*
+     Takes the command-line history of a device, determines what directories
+     had AT MOST 100k bytes, then sums up the total size of those directories.
+
+     See Part 1 of https://adventofcode.com/2022/day/7
+     */
+    let file_system: FileSystem = FileSystem::from_transcript(lines).unwrap();
+    file_system.directories()
+        .map(|each| each.size)
+        .filter(|size| *size <= 100000)
+        .sum()
+}
+
+fn process_lines2(lines: &[String]) -> u64 {
+    /*
+     Takes the command-line history of a device, determines the recursive 
+     sizes of all directories, then determines the smallest directory to delete
+     such that / will be no larger than 30_000_000 bytes.
+
+     See Part 2 of https://adventofcode.com/2022/day/7
+     */
+    let file_system: FileSystem = FileSystem::from_transcript(lines).unwrap();
+    let plan: DeletionPlan = file_system.plan_deletion(70000000, 30000000).unwrap();
+    plan.smallest_directory.map_or(0, |(_, size)| size)
 }
 
 #[cfg(test)]
@@ -340,6 +819,310 @@ mod tests {
         assert_eq!(process_lines2(&lines), 2948823);
     }
 
+    #[test]
+    fn test_go_up_one_level_root() {
+        assert_eq!(go_up_one_level("/"), "/".to_string());
+        assert_eq!(go_up_one_level("/my-dir/a.b/"), "/my-dir/".to_string());
+    }
+
+    #[test]
+    fn test_change_directory() {
+        assert_eq!(change_directory("/a/", "b"), "/a/b/");
+        assert_eq!(change_directory("/a/b/", "/c/d"), "/c/d/");
+        assert_eq!(change_directory("/a/b/", "/"), "/");
+        assert_eq!(change_directory("/", ".."), "/");
+        assert_eq!(change_directory("/a/", "../b/./c"), "/b/c/");
+        assert_eq!(change_directory("/", "two words"), "/two words/");
+    }
+
+    #[test]
+    fn test_real_world_names() {
+        let lines: Vec<String> = vec![
+            "$ cd ..", "$ ls", "dir my-dir", "dir two words", "10 a.txt",
+            "$ cd my-dir", "$ ls", "dir a.b", "200 notes v2.md",
+            "$ cd /my-dir/a.b", "$ ls", "3000 x",
+            "$ cd /two words", "$ ls", "40000 big file",
+            // listing the same directories again shouldn't count anything twice
+            "$ cd /", "$ ls", "dir my-dir", "dir two words", "10 a.txt",
+            "$ cd my-dir/a.b", "$ ls", "3000 x",
+        ].iter().map(|each| each.to_string()).collect();
+        let (directories, files) = parse_input(&lines);
+        assert_eq!(directories.len(), 4);
+        assert_eq!(files.len(), 4);
+        let file_system = FileSystem::from_transcript(&lines).unwrap();
+        assert_eq!(file_system.root().size, 43210);
+        assert_eq!(file_system.get("/my-dir/").unwrap().size, 3200);
+        assert_eq!(file_system.get("/my-dir/a.b/").unwrap().size, 3000);
+        assert_eq!(file_system.get("/two words/big file").unwrap().size, 40000);
+        assert_eq!(file_system.get("/my-dir/notes v2.md").unwrap().size, 200);
+    }
+
+    #[test]
+    fn test_file_system_short() {
+        let file_system = FileSystem::from_transcript(&read_lines("day07_input_short.txt")).unwrap();
+        assert_eq!(file_system.root().size, 48381165);
+        assert_eq!(file_system.get("/a/").unwrap().size, 94853);
+        assert_eq!(file_system.get("/a/e/").unwrap().size, 584);
+        assert_eq!(file_system.get("/d/").unwrap().size, 24933642);
+        assert_eq!(file_system.get("/a/e/i").unwrap().size, 584);
+        assert!(file_system.get("/a/x/").is_none());
+        let a = file_system.get("/a/").unwrap();
+        let names: Vec<&str> = a.children.iter().map(|each| file_system.node(*each).name.as_str()).collect();
+        assert_eq!(names, vec!["e", "f", "g", "h.lst"]);
+        assert_eq!(file_system.node(a.parent.unwrap()).path, "/");
+        assert_eq!(file_system.directories().count(), 4);
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let file_system = FileSystem::from_transcript(&read_lines("day07_input_short.txt")).unwrap();
+        assert_eq!(file_system.render_tree(), vec![
+            "- / (dir, size=48381165)",
+            "  - a (dir, size=94853)",
+            "    - e (dir, size=584)",
+            "      - i (file, size=584)",
+            "    - f (file, size=29116)",
+            "    - g (file, size=2557)",
+            "    - h.lst (file, size=62596)",
+            "  - b.txt (file, size=14848514)",
+            "  - c.dat (file, size=8504156)",
+            "  - d (dir, size=24933642)",
+            "    - d.ext (file, size=5626152)",
+            "    - d.log (file, size=8033020)",
+            "    - j (file, size=4060174)",
+            "    - k (file, size=7214296)",
+        ]);
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let file_system = FileSystem::from_transcript(&read_lines("day07_input_short.txt")).unwrap();
+        assert_eq!(file_system.disk_usage(), vec!["47M\t/", "24M\t/d/", "93K\t/a/", "584\t/a/e/"]);
+        let largest: Vec<&str> = file_system.largest_directories(2).iter().map(|each| each.path.as_str()).collect();
+        assert_eq!(largest, vec!["/", "/d/"]);
+        assert_eq!(human_size(1023), "1023");
+        assert_eq!(human_size(1024), "1.0K");
+        assert_eq!(human_size(1025), "1.1K");
+        assert_eq!(human_size(10 * 1024 + 1), "11K");
+        assert_eq!(human_size(1024 * 1024 - 1), "1.0M");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let lines: Vec<String> = ["$ ls", "dir say \"hi\"", "5 a\\b", "$ cd say \"hi\"", "$ ls"]
+            .iter().map(|each| each.to_string()).collect();
+        let file_system = FileSystem::from_transcript(&lines).unwrap();
+        assert_eq!(file_system.to_json(), concat!(
+            r#"{"name":"/","type":"dir","size":5,"children":["#,
+            r#"{"name":"a\\b","type":"file","size":5},"#,
+            r#"{"name":"say \"hi\"","type":"dir","size":0,"children":[]}]}"#));
+    }
+
+    #[test]
+    fn test_plan_deletion_short() {
+        let file_system = FileSystem::from_transcript(&read_lines("day07_input_short.txt")).unwrap();
+        let plan = file_system.plan_deletion(70000000, 30000000).unwrap();
+        assert_eq!(plan.needed, 8381165);
+        assert_eq!(plan.smallest_directory, Some(("/d/".to_string(), 24933642)));
+        assert_eq!(plan.best_directories, Some((vec!["/d/".to_string()], 24933642)));
+        let plan = file_system.plan_deletion(48381165, 94000).unwrap();
+        assert_eq!(plan.smallest_directory, Some(("/a/".to_string(), 94853)));
+        assert_eq!(plan.best_directories, Some((vec!["/a/".to_string()], 94853)));
+        // there's already enough space
+        let plan = file_system.plan_deletion(100000000, 30000000).unwrap();
+        assert_eq!((plan.needed, plan.smallest_directory, plan.best_directories), (0, None, Some((Vec::new(), 0))));
+        assert_eq!(file_system.plan_deletion(10, 20), Err(DeletionError::DiskTooSmall { disk_size: 10, required_free: 20 }));
+    }
+
+    #[test]
+    fn test_plan_deletion_sets() {
+        let mut file_system = FileSystem::new();
+        file_system.add_file("/a/x", 60);
+        file_system.add_file("/a/b/y", 50);
+        file_system.add_file("/c/z", 45);
+        file_system.add_file("/d/w", 100);
+        file_system.compute_sizes().unwrap();
+        // 95 is needed: /a/b/ and /c/ give exactly that, while the smallest
+        // single directory is /d/
+        let plan = file_system.plan_deletion(255, 95).unwrap();
+        assert_eq!(plan.needed, 95);
+        assert_eq!(plan.smallest_directory, Some(("/d/".to_string(), 100)));
+        assert_eq!(plan.best_directories, Some((vec!["/a/b/".to_string(), "/c/".to_string()], 95)));
+        // /a/ and /c/ together are exactly 155, but can't be combined with /a/b/
+        let plan = file_system.plan_deletion(255, 155).unwrap();
+        assert_eq!(plan.best_directories, Some((vec!["/a/".to_string(), "/c/".to_string()], 155)));
+        let plan = file_system.plan_deletion(255, 161).unwrap();
+        assert_eq!(plan.smallest_directory, Some(("/".to_string(), 255)));
+        assert_eq!(plan.best_directories, Some((vec!["/a/b/".to_string(), "/c/".to_string(), "/d/".to_string()], 195)));
+    }
+
+    #[test]
+    fn test_plan_deletion_against_brute_force() {
+        let mut file_system = FileSystem::new();
+        for (path, size) in [("/a/x", 7), ("/a/b/y", 13), ("/a/b/c/z", 29), ("/a/d/w", 3), ("/e/v", 41),
+            ("/e/f/u", 17), ("/g/t", 11), ("/g/h/s", 5), ("/g/h/i/r", 23), ("/q", 2)] {
+            file_system.add_file(path, size);
+        }
+        file_system.compute_sizes().unwrap();
+        let directories: Vec<&Node> = file_system.directories().collect();
+        let used: u64 = file_system.root().size;
+        for required_free in 1..=used {
+            let plan = file_system.plan_deletion(used, required_free).unwrap();
+            // try every set of directories that don't contain each other
+            let best: u64 = (0..1u32 << directories.len())
+                .filter(|set| (0..directories.len()).all(|a| (0..directories.len()).all(|b|
+                    a == b || set >> a & 1 == 0 || set >> b & 1 == 0 || !directories[b].path.starts_with(&directories[a].path))))
+                .map(|set| (0..directories.len()).filter(|each| set >> each & 1 == 1).map(|each| directories[each].size).sum())
+                .filter(|total| *total >= required_free)
+                .min()
+                .unwrap();
+            let (chosen, total) = plan.best_directories.unwrap();
+            assert_eq!(total, best, "{} bytes needed", required_free);
+            assert_eq!(chosen.iter().map(|each| file_system.get(each).unwrap().size).sum::<u64>(), total);
+        }
+    }
+
+    #[test]
+    fn test_plan_deletion_full() {
+        let file_system = FileSystem::from_transcript(&read_lines("day07_input.txt")).unwrap();
+        let plan = file_system.plan_deletion(70000000, 30000000).unwrap();
+        assert_eq!(plan.smallest_directory.unwrap().1, 2948823);
+        let (directories, total) = plan.best_directories.unwrap();
+        assert!(total >= plan.needed && total <= 2948823);
+        let sizes: u64 = directories.iter().map(|each| file_system.get(each).unwrap().size).sum();
+        assert_eq!(sizes, total);
+        for a in &directories {
+            for b in &directories {
+                assert!(a == b || !b.starts_with(a.as_str()));
+            }
+        }
+    }
+
+    fn make_fixture(name: &str) -> PathBuf {
+        // a small directory tree to compare transcripts against
+        let root: PathBuf = std::env::temp_dir().join(format!("aoc_2022_day07_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a").join("e")).unwrap();
+        fs::create_dir_all(root.join("my dir")).unwrap();
+        fs::write(root.join("b.txt"), vec![0; 1500]).unwrap();
+        fs::write(root.join("a").join("f"), vec![0; 200]).unwrap();
+        fs::write(root.join("a").join("e").join("i"), vec![0; 30]).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_transcript_from_directory() {
+        let root = make_fixture("transcript");
+        let transcript = transcript_from_directory(&root).unwrap();
+        assert_eq!(transcript, vec![
+            "$ cd /", "$ ls", "dir a", "1500 b.txt", "dir my dir",
+            "$ cd a", "$ ls", "dir e", "200 f",
+            "$ cd e", "$ ls", "30 i", "$ cd ..",
+            "$ cd ..",
+            "$ cd my dir", "$ ls", "$ cd ..",
+        ]);
+        assert_eq!(FileSystem::from_transcript(&transcript).unwrap().root().size, 1730);
+        assert!(diff_against_directory(&transcript, &root).unwrap().is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_diff_against_directory() {
+        let root = make_fixture("diff");
+        let lines: Vec<String> = [
+            "$ cd /", "$ ls", "dir a", "1500 b.txt", "dir c",
+            "$ cd a", "$ ls", "250 f",
+        ].iter().map(|each| each.to_string()).collect();
+        let differences = diff_against_directory(&lines, &root).unwrap();
+        assert_eq!(differences, vec![
+            Difference::Size { path: "/".to_string(), disk: 1730, transcript: 1750 },
+            Difference::Size { path: "/a/".to_string(), disk: 230, transcript: 250 },
+            Difference::Missing { path: "/a/e/".to_string() },
+            Difference::Missing { path: "/a/e/i".to_string() },
+            Difference::Size { path: "/a/f".to_string(), disk: 200, transcript: 250 },
+            Difference::Unexpected { path: "/c/".to_string() },
+            Difference::Missing { path: "/my dir/".to_string() },
+        ]);
+        assert_eq!(differences[4].to_string(), "/a/f is 200 bytes on disk but 250 bytes in the transcript");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_terabyte_sizes() {
+        let mut lines: Vec<String> = [
+            "$ cd /", "$ ls", "dir data", "dir logs", "4000000000000 disk.img",
+            "$ cd data", "$ ls", "3500000000000 a.bin", "2500000000000 b.bin",
+            "$ cd ../logs", "$ ls", "1 x.log",
+        ].iter().map(|each| each.to_string()).collect();
+        let file_system = FileSystem::from_transcript(&lines).unwrap();
+        assert_eq!(file_system.root().size, 10_000_000_000_001);
+        assert_eq!(file_system.get("/data/").unwrap().size, 6_000_000_000_000);
+        assert_eq!(file_system.disk_usage(), vec!["9.1T\t/", "5.5T\t/data/", "1\t/logs/"]);
+        assert_eq!(process_lines(&lines), 1);
+        let plan = file_system.plan_deletion(12_000_000_000_000, 5_000_000_000_000).unwrap();
+        assert_eq!(plan.needed, 3_000_000_000_001);
+        assert_eq!(plan.smallest_directory, Some(("/data/".to_string(), 6_000_000_000_000)));
+        assert_eq!(plan.best_directories, Some((vec!["/data/".to_string()], 6_000_000_000_000)));
+        // a thousand more directories of 3TB each, 3PB in all
+        lines.push("$ cd /".to_string());
+        for index in 0..1000 {
+            lines.push(format!("$ cd /disk{}", index));
+            lines.push("$ ls".to_string());
+            lines.push(format!("3000000000000 part{}.img", index));
+        }
+        let file_system = FileSystem::from_transcript(&lines).unwrap();
+        assert_eq!(file_system.root().size, 3_010_000_000_000_001);
+        assert_eq!(file_system.largest_directories(1)[0].path, "/");
+        // the search only has to keep track of the totals the directories can
+        // make, so petabytes are no harder than bytes: 7.5TB needs three 3TB
+        // directories, or /data/ and one of them
+        let plan = file_system.plan_deletion(3_010_000_000_000_001, 7_500_000_000_000).unwrap();
+        assert_eq!(plan.smallest_directory, Some(("/".to_string(), 3_010_000_000_000_001)));
+        let (directories, total) = plan.best_directories.unwrap();
+        assert_eq!(total, 9_000_000_000_000);
+        let sizes: u64 = directories.iter().map(|each| file_system.get(each).unwrap().size).sum();
+        assert_eq!(sizes, total);
+    }
+
+    #[test]
+    fn test_size_overflow() {
+        let lines: Vec<String> = [
+            "$ cd /", "$ ls", "dir a", "9223372036854775808 big",
+            "$ cd a", "$ ls", "9223372036854775808 also big",
+            // too big for a u64 even on its own, so not a file at all
+            "99999999999999999999999 huge",
+        ].iter().map(|each| each.to_string()).collect();
+        let (_, files) = parse_input(&lines);
+        assert_eq!(files.len(), 2);
+        let error = FileSystem::from_transcript(&lines).err().unwrap();
+        assert_eq!(error, SizeOverflow { path: "/".to_string() });
+        assert_eq!(error.to_string(), "the total size of / is too big to count");
+    }
+
+    #[test]
+    fn test_file_system_missing_parents() {
+        // directories that were never listed still show up in the tree
+        let mut file_system = FileSystem::new();
+        file_system.add_file("/x/y/z.txt", 10);
+        file_system.add_file("/x/w", 5);
+        file_system.compute_sizes().unwrap();
+        assert_eq!(file_system.get("/x/y/").unwrap().size, 10);
+        assert_eq!(file_system.get("/x/").unwrap().size, 15);
+        assert_eq!(file_system.root().size, 15);
+    }
+
+    #[test]
+    fn test_file_system_relative_paths() {
+        let mut file_system = FileSystem::new();
+        let file: usize = file_system.add_file("a", 1);
+        file_system.add_file("b/c", 2);
+        assert_eq!(file_system.add_file("/a", 1), file);
+        file_system.compute_sizes().unwrap();
+        assert_eq!(file_system.node(file).path, "/a");
+        assert_eq!(file_system.node(file).parent, Some(0));
+        assert_eq!(file_system.get("/b/").unwrap().size, 2);
+        assert_eq!(file_system.root().size, 3);
+    }
 }
 
 pub fn main() {
@@ -348,4 +1131,55 @@ pub fn main() {
     println!("Part 1 - The sum of the total sizes of those directories is: {}", process_lines(&result));
     println!("Part 2 - The smallest directory we can delete to free up enough space is {}", 
         process_lines2(&result));
+    let file_system: FileSystem = FileSystem::from_transcript(&result).unwrap();
+    if let Ok(DeletionPlan { needed, best_directories: Some((directories, total)), .. }) = file_system.plan_deletion(70000000, 30000000) {
+        println!("Deleting {} directories instead frees up {} bytes, {} more than needed", directories.len(), total, total - needed);
+    }
+    println!("The largest directories are:");
+    for each in file_system.disk_usage().iter().take(3) {
+        println!("    {}", each);
+    }
+    let depth = |node: &Node| {
+        let mut levels: usize = 0;
+        let mut current: &Node = node;
+        while let Some(parent) = current.parent {
+            current = file_system.node(parent);
+            levels += 1;
+        }
+        levels
+    };
+    if let Some(deepest) = file_system.directories().max_by_key(|each| depth(each)) {
+        println!("The deepest directory is {}, {} levels below /", deepest.path, depth(deepest));
+    }
+    // set DAY07_TREE to draw the whole tree, and DAY07_JSON to a file name to
+    // export it
+    if env::var_os("DAY07_TREE").is_some() {
+        for each_line in file_system.render_tree() {
+            println!("{}", each_line);
+        }
+    }
+    if let Some(path) = env::var_os("DAY07_JSON") {
+        match fs::write(&path, file_system.to_json()) {
+            Ok(()) => println!("The tree was exported to {}", Path::new(&path).display()),
+            Err(error) => eprintln!("Couldn't write {}: {}", Path::new(&path).display(), error),
+        }
+    }
+    // set DAY07_DIRECTORY to a real directory to check the transcript against it
+    if let Some(directory) = env::var_os("DAY07_DIRECTORY") {
+        let root: &Path = Path::new(&directory);
+        match transcript_from_directory(root) {
+            Ok(transcript) => println!("A transcript of {} takes {} lines", root.display(), transcript.len()),
+            Err(error) => eprintln!("Couldn't read {}: {}", root.display(), error),
+        }
+        match diff_against_directory(&result, root) {
+            Ok(differences) => {
+                println!("The transcript differs from {} in {} places", root.display(), differences.len());
+                for each in differences.iter().take(10) {
+                    println!("    {}", each);
+                }
+            },
+            Err(error) => eprintln!("Couldn't compare the transcript with {}: {}", root.display(), error),
+        }
+    }
+    println!();
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
+use std::env;
 use std::fs;
-use std::str::FromStr;
+use std::io;
+use std::path::Path;
+use crate::grid::{Grid, ORTHOGONAL, ALL_DIRECTIONS};
 
 fn read_lines(filename: &str) -> Vec<String> {
     /*
@@ -15,153 +18,170 @@ fn read_lines(filename: &str) -> Vec<String> {
     lines
 }
 
-fn process_lines(lines: &Vec<String>) -> i32 {
-    /*
-    Takes a grid of tree heights and determines how many are visible from
-    outside the grid.
+fn parse_trees(lines: &[String]) -> Grid<i32> {
+    Grid::parse(lines, |c| c.to_digit(10).map(|height| height as i32)).unwrap()
+}
 
-    See Part 1 of https://adventofcode.com/2022/day/8
-    */
-    let mut visible_trees: i32 = 0;
-    let mut tree_grid: Vec<Vec<(i32, bool)>> = Vec::new();
-    // populate the tree grid
-    for each_line in lines {
-        let mut next_row: Vec<(i32, bool)> = Vec::new();
-        for each_tree in each_line.chars() {
-            next_row.push((i32::from_str(each_tree.to_string().as_str()).unwrap(), false));
-        }
-        tree_grid.push(next_row);
-    }
-
-    // mark trees as visible looking from North
-    for x_index in 0..tree_grid.len() {
-        let mut previous_height: i32 = -1;
-        for y_index in 0..tree_grid.len() {
-            if tree_grid[y_index][x_index].0 > previous_height {
-                //println!("({}, {}) is visible because height {} > height {}", x_index, y_index,
-                //    tree_grid[y_index][x_index].0, previous_height);
-                previous_height = tree_grid[y_index][x_index].0;
-                tree_grid[y_index][x_index].1 = true;
-            }
-        }
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectionSet {
+    Orthogonal,
+    WithDiagonals,
+}
 
-    // mark trees as visible looking from South
-    for x_index in 0..tree_grid.len() {
-        let mut previous_height: i32 = -1;
-        for y_index in 0..tree_grid.len() {
-            let y_offset = tree_grid.len() - 1 - y_index;
-            if tree_grid[y_offset][x_index].0 > previous_height {
-                previous_height = tree_grid[y_offset][x_index].0;
-                tree_grid[y_offset][x_index].1 = true;
-            }
+impl DirectionSet {
+    pub fn directions(&self) -> &'static [(isize, isize)] {
+        /*
+        Looking West, East, North and South, then North-West, North-East,
+        South-West and South-East, the same order as the grid's neighbours
+        and TreeView::distances().
+        */
+        match self {
+            DirectionSet::Orthogonal => &ORTHOGONAL,
+            DirectionSet::WithDiagonals => &ALL_DIRECTIONS,
         }
     }
+}
 
-    // mark trees as visible looking from East
-    for y_index in 0..tree_grid.len() {
-        let mut previous_height: i32 = -1;
-        for x_index in 0..tree_grid.len() {
-            if tree_grid[y_index][x_index].0 > previous_height {
-                previous_height = tree_grid[y_index][x_index].0;
-                tree_grid[y_index][x_index].1 = true;
-            }
-        }
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blocking {
+    // any tree at least as tall as the observer's blocks the view
+    EqualOrTaller,
+    // only trees taller than the observer's block the view
+    StrictlyTaller,
+    // the observer's eyes are this far above the top of their own tree, and
+    // any tree reaching eye level blocks the view. An offset of 0 is the
+    // same as EqualOrTaller, and 1 the same as StrictlyTaller.
+    ObserverOffset(i32),
+}
 
-    // mark trees as visible looking from West
-    for y_index in 0..tree_grid.len() {
-        let mut previous_height: i32 = -1;
-        for x_index in 0..tree_grid.len() {
-            let x_offset = tree_grid.len() - 1 - x_index;
-            if tree_grid[y_index][x_offset].0 > previous_height {
-                previous_height = tree_grid[y_index][x_offset].0;
-                tree_grid[y_index][x_offset].1 = true;
-            }
+impl Blocking {
+    fn threshold(&self, height: i32) -> i32 {
+        /*
+        The height a tree has to reach to block the view from a tree of the
+        given height. A threshold too high to fit in an i32 is capped at
+        i32::MAX, which no tree in a puzzle input gets anywhere near.
+        */
+        match self {
+            Blocking::EqualOrTaller => height,
+            Blocking::StrictlyTaller => height.saturating_add(1),
+            Blocking::ObserverOffset(offset) => height.saturating_add(*offset),
         }
     }
+}
 
-    // count the visible trees
-    for y_index in 0..tree_grid.len() {
-        for x_index in 0..tree_grid.len() {
-            if tree_grid[y_index][x_index].1 {
-                visible_trees += 1;
-                //print!("T");
-            }else {
-                //print!("F");
-            }
-        }
-        //println!("");
-    }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ViewRules {
+    pub directions: DirectionSet,
+    pub blocking: Blocking,
+}
 
-    visible_trees
+impl ViewRules {
+    // the rules from the puzzle
+    pub const PUZZLE: ViewRules = ViewRules { directions: DirectionSet::Orthogonal, blocking: Blocking::EqualOrTaller };
 }
 
-fn check_to_north(tree_grid: &Vec<Vec<(i32, i32)>>, x_index: usize, y_index: usize) -> i32 {
-    // check trees to the North
-    let tree_height: i32 = tree_grid[y_index][x_index].0;
-    let mut sub_score: i32 = 0;
-    for inner_y_index in 1..y_index+1 {
-        //println!("Comparing ({}, {}) with ({}, {})", x_index, y_index, x_index, y_index - inner_y_index);
-        if tree_grid[y_index - inner_y_index][x_index].0 < tree_height {
-            sub_score += 1;
-        }else {
-            sub_score += 1;
-            break;
-        }
+impl Default for ViewRules {
+    fn default() -> ViewRules {
+        ViewRules::PUZZLE
     }
-    sub_score
 }
 
-fn check_to_south(tree_grid: &Vec<Vec<(i32, i32)>>, x_index: usize, y_index: usize) -> i32 {
-    // check trees to the South
-    let tree_height: i32 = tree_grid[y_index][x_index].0;
-    let mut sub_score: i32 = 0;
-    for inner_y_index in y_index+1..tree_grid.len() {
-        if tree_grid[inner_y_index][x_index].0 < tree_height {
-            sub_score += 1;
-        }else {
-            sub_score += 1;
-            break;
-        }
-    }
-    sub_score
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TreeView {
+    // whether the tree can be seen from outside the grid in any direction
+    pub visible: bool,
+    distances: [u32; ALL_DIRECTIONS.len()],
+    direction_count: usize,
 }
 
-fn check_to_east(tree_grid: &Vec<Vec<(i32, i32)>>, x_index: usize, y_index: usize) -> i32 {
-    // check trees to the East
-    let tree_height: i32 = tree_grid[y_index][x_index].0;
-    let mut sub_score: i32 = 0;
-    for inner_x_index in x_index+1..tree_grid.len() {
-        //println!("Comparing ({}, {}) with ({}, {})", x_index, y_index, inner_x_index, y_index);
-        if tree_grid[y_index][inner_x_index].0 < tree_height {
-            sub_score += 1;
-        }else {
-            sub_score += 1;
-            break;
-        }
+impl TreeView {
+    pub fn distances(&self) -> &[u32] {
+        /*
+        How many trees can be seen from this one looking in each direction of
+        the rules it was worked out with, up to and including the first one
+        that blocks the view.
+        */
+        &self.distances[..self.direction_count]
+    }
+
+    pub fn scenic_score(&self) -> u64 {
+        self.distances().iter().map(|each| *each as u64).product()
     }
-    sub_score
 }
 
-fn check_to_west(tree_grid: &Vec<Vec<(i32, i32)>>, x_index: usize, y_index: usize) -> i32 {
-    // check trees to the West
-    let tree_height: i32 = tree_grid[y_index][x_index].0;
-    let mut sub_score: i32 = 0;
-    for inner_x_index in 1..x_index+1 {
-        //println!("Comparing ({}, {}) with ({}, {})", x_index, y_index, x_index - inner_x_index, y_index);
-        if tree_grid[y_index][x_index - inner_x_index].0 < tree_height {
-            sub_score += 1;
-        }else {
-            sub_score += 1;
-            break;
+pub fn analyze_forest(tree_grid: &Grid<i32>) -> Grid<TreeView> {
+    analyze_forest_with(tree_grid, &ViewRules::PUZZLE)
+}
+
+pub fn analyze_forest_with(tree_grid: &Grid<i32>, rules: &ViewRules) -> Grid<TreeView> {
+    /*
+    Work out the visibility and viewing distances of every tree in the grid.
+    For each direction, walk every line of trees starting from the edge the
+    trees are looking at, keeping a stack of the trees that could still block
+    the view of a later one. A tree that is no taller than a later one can
+    never block anything the later one doesn't, so the stack only ever holds
+    trees that get shorter towards the top, and the nearest tree tall enough
+    to block the view is the topmost one reaching the threshold. If there
+    isn't one, the tree can be seen from that edge and the viewing distance
+    runs all the way to it.
+
+    With a threshold no more than one above the tree's own height (which
+    covers the puzzle's rules), the answer is always on top once the trees
+    that are no taller have been popped, so the whole thing is O(W*H). Larger
+    observer offsets need a binary search down the stack instead.
+    */
+    let directions: &[(isize, isize)] = rules.directions.directions();
+    let blank: TreeView = TreeView { direction_count: directions.len(), ..TreeView::default() };
+    let mut views: Grid<TreeView> = Grid::new(tree_grid.width(), tree_grid.height(), blank);
+    let mut stack: Vec<(i32, u32)> = Vec::new();
+    for (direction_index, direction) in directions.iter().enumerate() {
+        let backwards: (isize, isize) = (-direction.0, -direction.1);
+        let edges: Vec<(usize, usize)> = tree_grid.positions()
+            .filter(|each| tree_grid.step(*each, *direction).is_none())
+            .collect();
+        for edge in edges {
+            stack.clear();
+            let line = std::iter::once(edge).chain(tree_grid.ray(edge, backwards));
+            for (index, position) in (0u32..).zip(line) {
+                let height: i32 = tree_grid[position];
+                let threshold: i32 = rules.blocking.threshold(height);
+                while stack.last().is_some_and(|(blocker, _)| *blocker < threshold.min(height.saturating_add(1))) {
+                    stack.pop();
+                }
+                let blocker: Option<u32> = match stack.last() {
+                    Some((top, top_index)) if *top >= threshold => Some(*top_index),
+                    _ => stack[..stack.partition_point(|(each, _)| *each >= threshold)].last().map(|(_, each)| *each),
+                };
+                let view: &mut TreeView = &mut views[position];
+                match blocker {
+                    Some(blocker_index) => view.distances[direction_index] = index - blocker_index,
+                    None => {
+                        view.distances[direction_index] = index;
+                        view.visible = true;
+                    }
+                }
+                while stack.last().is_some_and(|(each, _)| *each <= height) {
+                    stack.pop();
+                }
+                stack.push((height, index));
+            }
         }
     }
-    sub_score
+    views
 }
 
+fn process_lines(lines: &[String]) -> i32 {
+    /*
+    Takes a grid of tree heights and determines how many are visible from
+    outside the grid.
 
-fn process_lines2(lines: &Vec<String>) -> i32 {
+    See Part 1 of https://adventofcode.com/2022/day/8
+    */
+    let views: Grid<TreeView> = analyze_forest(&parse_trees(lines));
+    views.iter().filter(|(_, each)| each.visible).count() as i32
+}
+
+fn process_lines2(lines: &[String]) -> u64 {
     /*
     Takes a grid of tree heights and determines the scenic score of each tree,
     returning the highest score found. To calculate the scenic score of a tree,
@@ -172,42 +192,280 @@ fn process_lines2(lines: &Vec<String>) -> i32 {
 
     See Part 2 of https://adventofcode.com/2022/day/8
     */
-    let mut heighest_score: i32 = 0;
-    let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-    // populate the tree grid
-    for each_line in lines {
-        let mut next_row: Vec<(i32, i32)> = Vec::new();
-        for each_tree in each_line.chars() {
-            next_row.push((i32::from_str(each_tree.to_string().as_str()).unwrap(), 0));
-        }
-        tree_grid.push(next_row);
+    let views: Grid<TreeView> = analyze_forest(&parse_trees(lines));
+    views.iter().map(|(_, each)| each.scenic_score()).max().unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layer {
+    // 1 for trees that can be seen from outside the grid, 0 otherwise
+    Visibility,
+    ScenicScore,
+}
+
+// darkest to brightest, for the ASCII previews
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+fn layer_values(views: &Grid<TreeView>, layer: Layer) -> Grid<u64> {
+    views.map(|each| match layer {
+        Layer::Visibility => each.visible as u64,
+        Layer::ScenicScore => each.scenic_score(),
+    })
+}
+
+fn scale(value: u64, maximum: u64, top: u64) -> u64 {
+    /*
+    Scale value from 0..=maximum down to 0..=top, going through u128 so that
+    large scenic scores can't overflow.
+    */
+    if maximum == 0 {
+        return 0;
     }
+    (value as u128 * top as u128 / maximum as u128) as u64
+}
 
-    // visit each tree in the grid
-    for x_index in 1..tree_grid.len()-1 {
-        for y_index in 1..tree_grid.len()-1 {
-            let mut tree_scores: (i32, i32, i32, i32) = (0, 0, 0, 0);
+pub fn to_csv(views: &Grid<TreeView>, layer: Layer) -> String {
+    /*
+    One line per row of trees, with the raw values separated by commas.
+    */
+    layer_values(views, layer).rows()
+        .map(|each_row| each_row.iter().map(|each| each.to_string()).collect::<Vec<String>>().join(",") + "\n")
+        .collect()
+}
 
-            tree_scores.1 += check_to_north(&tree_grid, x_index, y_index);
-            tree_scores.3 += check_to_south(&tree_grid, x_index, y_index);
-            tree_scores.0 += check_to_east(&tree_grid, x_index, y_index);
-            tree_scores.2 += check_to_west(&tree_grid, x_index, y_index);
-            tree_grid[y_index][x_index].1 = tree_scores.0 * tree_scores.1 * tree_scores.2 * tree_scores.3;
+pub fn to_pgm(views: &Grid<TreeView>, layer: Layer) -> String {
+    /*
+    A plain (ASCII) greyscale PGM image, one pixel per tree, with the values
+    scaled so that the highest one is white.
+    */
+    let values: Grid<u64> = layer_values(views, layer);
+    let maximum: u64 = values.iter().map(|(_, each)| *each).max().unwrap_or(0);
+    let mut result: String = format!("P2\n{} {}\n255\n", values.width(), values.height());
+    for each_row in values.rows() {
+        let pixels: Vec<String> = each_row.iter().map(|each| scale(*each, maximum, 255).to_string()).collect();
+        result.push_str(&pixels.join(" "));
+        result.push('\n');
+    }
+    result
+}
 
-            //println!("({}, {}) has scenic score {}*{}*{}*{} = {}.", x_index, y_index, 
-            //    tree_scores.1, tree_scores.0, tree_scores.3, tree_scores.2, tree_grid[y_index][x_index].1);
-            if tree_grid[y_index][x_index].1 > heighest_score {
-                heighest_score = tree_grid[y_index][x_index].1;
-            }
-        }
+pub fn to_ppm(views: &Grid<TreeView>) -> String {
+    /*
+    A plain (ASCII) colour PPM image showing both layers at once: the red
+    channel is the scenic score, scaled so the highest is brightest, and the
+    green channel is full for visible trees and empty for hidden ones.
+    */
+    let maximum: u64 = views.iter().map(|(_, each)| each.scenic_score()).max().unwrap_or(0);
+    let mut result: String = format!("P3\n{} {}\n255\n", views.width(), views.height());
+    for each_row in views.rows() {
+        let pixels: Vec<String> = each_row.iter()
+            .map(|each| format!("{} {} 0", scale(each.scenic_score(), maximum, 255), if each.visible { 255 } else { 0 }))
+            .collect();
+        result.push_str(&pixels.join("  "));
+        result.push('\n');
     }
+    result
+}
 
-    heighest_score
+pub fn ascii_preview(views: &Grid<TreeView>, layer: Layer) -> Vec<String> {
+    /*
+    One character per tree, shaded from ' ' for the lowest values up to '@'
+    for the highest.
+    */
+    let values: Grid<u64> = layer_values(views, layer);
+    let maximum: u64 = values.iter().map(|(_, each)| *each).max().unwrap_or(0);
+    let top: u64 = SHADES.len() as u64 - 1;
+    values.rows()
+        .map(|each_row| each_row.iter().map(|each| SHADES[scale(*each, maximum, top) as usize] as char).collect())
+        .collect()
+}
+
+pub fn export_heatmap(views: &Grid<TreeView>, layer: Layer, path: &Path) -> io::Result<()> {
+    /*
+    Write a heatmap to a file, picking the format from its extension: .csv,
+    .pgm or .ppm (which always shows both layers).
+    */
+    let contents: String = match path.extension().and_then(|each| each.to_str()) {
+        Some("csv") => to_csv(views, layer),
+        Some("pgm") => to_pgm(views, layer),
+        Some("ppm") => to_ppm(views),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+            format!("don't know how to write a heatmap to {}", path.display()))),
+    };
+    fs::write(path, contents)
+}
+
+pub fn write_heatmaps(views: &Grid<TreeView>, directory: &Path) -> io::Result<()> {
+    /*
+    Write every heatmap of the forest into a directory: both layers as CSV
+    and PGM, and the combined PPM.
+    */
+    fs::create_dir_all(directory)?;
+    for (layer, name) in [(Layer::Visibility, "visibility"), (Layer::ScenicScore, "scenic_score")] {
+        export_heatmap(views, layer, &directory.join(format!("{}.csv", name)))?;
+        export_heatmap(views, layer, &directory.join(format!("{}.pgm", name)))?;
+    }
+    export_heatmap(views, Layer::ScenicScore, &directory.join("forest.ppm"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::day02_simulator::Rng;
+
+    // the straightforward walk away from a single tree, kept to check
+    // analyze_forest() against
+    fn viewing_distance(tree_grid: &Grid<i32>, x_index: usize, y_index: usize, direction: (isize, isize)) -> i32 {
+        let tree_height: i32 = tree_grid[(x_index, y_index)];
+        let mut sub_score: i32 = 0;
+        for position in tree_grid.ray((x_index, y_index), direction) {
+            sub_score += 1;
+            if tree_grid[position] >= tree_height {
+                break;
+            }
+        }
+        sub_score
+    }
+
+    fn check_to_north(tree_grid: &Grid<i32>, x_index: usize, y_index: usize) -> i32 {
+        viewing_distance(tree_grid, x_index, y_index, (0, -1))
+    }
+
+    fn check_to_south(tree_grid: &Grid<i32>, x_index: usize, y_index: usize) -> i32 {
+        viewing_distance(tree_grid, x_index, y_index, (0, 1))
+    }
+
+    fn check_to_east(tree_grid: &Grid<i32>, x_index: usize, y_index: usize) -> i32 {
+        viewing_distance(tree_grid, x_index, y_index, (1, 0))
+    }
+
+    fn check_to_west(tree_grid: &Grid<i32>, x_index: usize, y_index: usize) -> i32 {
+        viewing_distance(tree_grid, x_index, y_index, (-1, 0))
+    }
+
+    fn synthetic_forest(width: usize, height: usize, seed: u64) -> Grid<i32> {
+        let mut rng: Rng = Rng::new(seed);
+        let rows: Vec<Vec<i32>> = (0..height).map(|_| (0..width).map(|_| rng.next_below(10) as i32).collect()).collect();
+        Grid::from_rows(rows).unwrap()
+    }
+
+    #[test]
+    fn test_analyze_forest_example() {
+        let lines = read_lines("day08_input_short.txt");
+        let views: Grid<TreeView> = analyze_forest(&parse_trees(&lines));
+        // the middle 5 in the second row, and the 5 in the fourth row
+        assert_eq!(views[(2, 1)].distances(), [1, 2, 1, 2]);
+        assert_eq!(views[(2, 3)].distances(), [2, 2, 2, 1]);
+        assert!(views[(2, 1)].visible && views[(2, 3)].visible);
+        assert_eq!(views[(2, 3)].scenic_score(), 8);
+        // the 3 in the middle can't be seen from anywhere
+        assert!(!views[(2, 2)].visible);
+    }
+
+    #[test]
+    fn test_analyze_forest_matches_walk() {
+        for (seed, (width, height)) in [(7, 5), (1, 9), (12, 12), (30, 2), (17, 23)].into_iter().enumerate() {
+            let tree_grid: Grid<i32> = synthetic_forest(width, height, seed as u64);
+            let views: Grid<TreeView> = analyze_forest(&tree_grid);
+            for (x_index, y_index) in tree_grid.positions() {
+                let expected: [i32; 4] = [check_to_west(&tree_grid, x_index, y_index),
+                                          check_to_east(&tree_grid, x_index, y_index),
+                                          check_to_north(&tree_grid, x_index, y_index),
+                                          check_to_south(&tree_grid, x_index, y_index)];
+                let visible: bool = ORTHOGONAL.iter().any(|direction| tree_grid.ray((x_index, y_index), *direction)
+                    .all(|each| tree_grid[each] < tree_grid[(x_index, y_index)]));
+                let distances: Vec<i32> = views[(x_index, y_index)].distances().iter().map(|each| *each as i32).collect();
+                assert_eq!(distances, expected);
+                assert_eq!(views[(x_index, y_index)].visible, visible);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rules_match_walk() {
+        // walk away from each tree until something reaches the blocking
+        // threshold, for every combination of rules
+        let blocking_rules: Vec<Blocking> = [Blocking::EqualOrTaller, Blocking::StrictlyTaller].into_iter()
+            .chain((-2..=4).map(Blocking::ObserverOffset))
+            .collect();
+        for (seed, (width, height)) in [(9, 7), (1, 6), (13, 13), (20, 3)].into_iter().enumerate() {
+            let tree_grid: Grid<i32> = synthetic_forest(width, height, seed as u64 + 100);
+            for directions in [DirectionSet::Orthogonal, DirectionSet::WithDiagonals] {
+                for blocking in &blocking_rules {
+                    let rules: ViewRules = ViewRules { directions, blocking: *blocking };
+                    let views: Grid<TreeView> = analyze_forest_with(&tree_grid, &rules);
+                    for position in tree_grid.positions() {
+                        let threshold: i32 = blocking.threshold(tree_grid[position]);
+                        let mut visible: bool = false;
+                        let mut distances: Vec<u32> = Vec::new();
+                        for direction in directions.directions() {
+                            let ray: Vec<(usize, usize)> = tree_grid.ray(position, *direction).collect();
+                            match ray.iter().position(|each| tree_grid[*each] >= threshold) {
+                                Some(index) => distances.push(index as u32 + 1),
+                                None => {
+                                    distances.push(ray.len() as u32);
+                                    visible = true;
+                                }
+                            }
+                        }
+                        assert_eq!(views[position].distances(), distances, "{:?} at {:?}", rules, position);
+                        assert_eq!(views[position].visible, visible, "{:?} at {:?}", rules, position);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_view_rules() {
+        let lines = read_lines("day08_input_short.txt");
+        let tree_grid: Grid<i32> = parse_trees(&lines);
+        assert_eq!(ViewRules::default(), ViewRules::PUZZLE);
+
+        // the 3 in the middle can see the 3 in the top-right corner diagonally
+        let rules = ViewRules { directions: DirectionSet::WithDiagonals, ..ViewRules::default() };
+        let views: Grid<TreeView> = analyze_forest_with(&tree_grid, &rules);
+        assert_eq!(views[(2, 2)].distances(), [1, 1, 1, 1, 1, 2, 1, 1]);
+        assert_eq!(views[(2, 2)].scenic_score(), 2);
+        assert!(!views[(2, 2)].visible);
+
+        // trees of the same height no longer block each other
+        let flat: Grid<i32> = Grid::new(3, 3, 1);
+        let rules = ViewRules { blocking: Blocking::StrictlyTaller, ..ViewRules::default() };
+        let views: Grid<TreeView> = analyze_forest_with(&flat, &rules);
+        assert!(views.iter().all(|(_, each)| each.visible));
+        assert_eq!(views[(1, 1)].distances(), [1, 1, 1, 1]);
+        assert_eq!(analyze_forest_with(&flat, &ViewRules::PUZZLE)[(1, 1)].distances(), [1, 1, 1, 1]);
+        assert!(!analyze_forest(&flat)[(1, 1)].visible);
+
+        // looking from 2 above the top of the 5 in the second row, nothing
+        // reaches eye level, so it can see all the way to every edge
+        let rules = ViewRules { blocking: Blocking::ObserverOffset(2), ..ViewRules::default() };
+        let views: Grid<TreeView> = analyze_forest_with(&tree_grid, &rules);
+        assert_eq!(views[(1, 1)].distances(), [1, 3, 1, 3]);
+        assert_eq!(views[(1, 1)].scenic_score(), 9);
+
+        // an offset this large can't overflow the threshold
+        let rules = ViewRules { blocking: Blocking::ObserverOffset(i32::MAX), ..ViewRules::default() };
+        let views: Grid<TreeView> = analyze_forest_with(&tree_grid, &rules);
+        assert!(views.iter().all(|(_, each)| each.visible));
+        assert_eq!(views[(1, 1)].distances(), [1, 3, 1, 3]);
+        assert_eq!(Blocking::StrictlyTaller.threshold(i32::MAX), i32::MAX);
+    }
+
+    #[test]
+    fn test_large_forest() {
+        // a pyramid peaking in the middle: every tree can be seen, and the
+        // peak can see all the way to each edge
+        let size: usize = 1999;
+        let rows: Vec<Vec<i32>> = (0..size).map(|y| (0..size).map(|x| {
+            let from_edge: usize = x.min(y).min(size - 1 - x).min(size - 1 - y);
+            from_edge as i32
+        }).collect()).collect();
+        let views: Grid<TreeView> = analyze_forest(&Grid::from_rows(rows).unwrap());
+        assert!(views.iter().all(|(_, each)| each.visible));
+        assert_eq!(views[(999, 999)].scenic_score(), 999u64.pow(4));
+    }
 
     #[test]
     fn test_process_lines_short() {
@@ -233,146 +491,223 @@ mod tests {
         assert_eq!(process_lines2(&lines), 345744);
     }
 
+    fn to_lines(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|each| each.to_string()).collect()
+    }
+
+    #[test]
+    fn test_wide_grid() {
+        let lines = to_lines(&["30373", "25512", "65332"]);
+        assert_eq!(process_lines(&lines), 14);
+        assert_eq!(process_lines2(&lines), 2);
+    }
+
+    #[test]
+    fn test_tall_grid() {
+        let lines = to_lines(&["303", "255", "653", "333", "353"]);
+        assert_eq!(process_lines(&lines), 14);
+        assert_eq!(process_lines2(&lines), 2);
+        let lines = to_lines(&["1111", "1911", "1111"]);
+        assert_eq!(process_lines(&lines), 11);
+        assert_eq!(process_lines2(&lines), 2);
+    }
+
+    #[test]
+    fn test_single_row_and_column() {
+        let lines = to_lines(&["30373"]);
+        assert_eq!(process_lines(&lines), 5);
+        assert_eq!(process_lines2(&lines), 0);
+        let lines = to_lines(&["3", "0", "3", "7"]);
+        assert_eq!(process_lines(&lines), 4);
+        assert_eq!(process_lines2(&lines), 0);
+        let lines = to_lines(&["9"]);
+        assert_eq!(process_lines(&lines), 1);
+        assert_eq!(process_lines2(&lines), 0);
+    }
+
+    #[test]
+    fn test_empty_grid() {
+        assert_eq!(process_lines(&[]), 0);
+        assert_eq!(process_lines2(&[]), 0);
+        assert_eq!(process_lines(&to_lines(&[""])), 0);
+    }
+
+    #[test]
+    fn test_heatmaps() {
+        let lines = read_lines("day08_input_short.txt");
+        let views: Grid<TreeView> = analyze_forest(&parse_trees(&lines));
+        assert_eq!(to_csv(&views, Layer::ScenicScore), "0,0,0,0,0\n0,1,4,1,0\n0,6,1,2,0\n0,1,8,3,0\n0,0,0,0,0\n");
+        assert_eq!(to_csv(&views, Layer::Visibility), "1,1,1,1,1\n1,1,1,0,1\n1,1,0,1,1\n1,0,1,0,1\n1,1,1,1,1\n");
+        assert_eq!(to_pgm(&views, Layer::ScenicScore),
+            "P2\n5 5\n255\n0 0 0 0 0\n0 31 127 31 0\n0 191 31 63 0\n0 31 255 95 0\n0 0 0 0 0\n");
+        let ppm: String = to_ppm(&views);
+        assert!(ppm.starts_with("P3\n5 5\n255\n0 255 0  0 255 0"));
+        // the hidden tree in the middle, with a scenic score of 1
+        assert_eq!(ppm.lines().nth(5).unwrap(), "0 255 0  191 255 0  31 0 0  63 255 0  0 255 0");
+        assert_eq!(ascii_preview(&views, Layer::ScenicScore), vec!["     ", " .=. ", " *.: ", " .@- ", "     "]);
+        assert_eq!(ascii_preview(&views, Layer::Visibility), vec!["@@@@@", "@@@ @", "@@ @@", "@ @ @", "@@@@@"]);
+    }
+
+    #[test]
+    fn test_empty_heatmaps() {
+        let views: Grid<TreeView> = analyze_forest(&parse_trees(&[]));
+        assert_eq!(to_csv(&views, Layer::ScenicScore), "");
+        assert_eq!(to_pgm(&views, Layer::Visibility), "P2\n0 0\n255\n");
+        assert!(ascii_preview(&views, Layer::ScenicScore).is_empty());
+    }
+
+    #[test]
+    fn test_export_heatmap() {
+        let views: Grid<TreeView> = analyze_forest(&parse_trees(&to_lines(&["123", "456"])));
+        let path = std::env::temp_dir().join(format!("aoc_2022_day08_{}.csv", std::process::id()));
+        export_heatmap(&views, Layer::Visibility, &path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1,1,1\n1,1,1\n");
+        fs::remove_file(&path).unwrap();
+        let error = export_heatmap(&views, Layer::Visibility, Path::new("heatmap.png")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_write_heatmaps() {
+        let views: Grid<TreeView> = analyze_forest(&parse_trees(&to_lines(&["123", "456"])));
+        let directory = std::env::temp_dir().join(format!("aoc_2022_day08_heatmaps_{}", std::process::id()));
+        write_heatmaps(&views, &directory).unwrap();
+        let mut names: Vec<String> = fs::read_dir(&directory).unwrap()
+            .map(|each| each.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["forest.ppm", "scenic_score.csv", "scenic_score.pgm", "visibility.csv", "visibility.pgm"]);
+        assert_eq!(fs::read_to_string(directory.join("visibility.csv")).unwrap(), to_csv(&views, Layer::Visibility));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
     #[test]
     fn test_check_to_north_01() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_north(&tree_grid, 2, 1), 1);
     }
 
     #[test]
     fn test_check_to_north_02() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_north(&tree_grid, 4, 3), 3);
     }
 
     #[test]
     fn test_check_to_north_03() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_north(&tree_grid, 2, 3), 2);
     }
 
     #[test]
     fn test_check_to_east_01() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_east(&tree_grid, 1, 2), 3);
     }
 
     #[test]
     fn test_check_to_east_02() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_east(&tree_grid, 3, 1), 1);
     }
 
     #[test]
     fn test_check_to_east_03() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_east(&tree_grid, 3, 2), 1);
     }
 
     #[test]
     fn test_check_to_east_04() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_east(&tree_grid, 2, 1), 2);
     }
 
     #[test]
     fn test_check_to_east_05() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_east(&tree_grid, 2, 3), 2);
     }
 
     #[test]
     fn test_check_to_south_01() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_south(&tree_grid, 3, 0), 4);
     }
 
     #[test]
     fn test_check_to_south_02() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_south(&tree_grid, 4, 3), 1);
     }
 
     #[test]
     fn test_check_to_west_01() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_west(&tree_grid, 4, 1), 2);
     }
 
     #[test]
     fn test_check_to_west_02() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_west(&tree_grid, 3, 0), 3);
     }
 
     #[test]
     fn test_check_to_west_03() {
-        let mut tree_grid: Vec<Vec<(i32, i32)>> = Vec::new();
-        tree_grid.push(vec![(3, 0), (0, 0), (3, 0), (7, 0), (3, 0)]);
-        tree_grid.push(vec![(2, 0), (5, 0), (5, 0), (1, 0), (2, 0)]);
-        tree_grid.push(vec![(6, 0), (5, 0), (3, 0), (3, 0), (2, 0)]);
-        tree_grid.push(vec![(3, 0), (3, 0), (5, 0), (4, 0), (9, 0)]);
-        tree_grid.push(vec![(3, 0), (5, 0), (3, 0), (9, 0), (0, 0)]);
+        let tree_grid: Grid<i32> = Grid::from_rows(vec![vec![3, 0, 3, 7, 3],
+                                                        vec![2, 5, 5, 1, 2],
+                                                        vec![6, 5, 3, 3, 2],
+                                                        vec![3, 3, 5, 4, 9],
+                                                        vec![3, 5, 3, 9, 0]]).unwrap();
         assert_eq!(check_to_west(&tree_grid, 1, 1), 1);
     }
 }
@@ -382,4 +717,40 @@ pub fn main() {
     println!("Day 8:");
     println!("Part 1 - The number of visible trees: {}", process_lines(&result));
     println!("Part 2 - The highest scenic score possible for any tree is: {}", process_lines2(&result));
+    let trees: Grid<i32> = parse_trees(&result);
+    for (description, rules) in [
+        ("looking diagonally as well", ViewRules { directions: DirectionSet::WithDiagonals, ..ViewRules::PUZZLE }),
+        ("if only taller trees block the view", ViewRules { blocking: Blocking::StrictlyTaller, ..ViewRules::PUZZLE }),
+        ("with the elves' eyes 2 above the treetops", ViewRules { blocking: Blocking::ObserverOffset(2), ..ViewRules::PUZZLE }),
+    ] {
+        let views: Grid<TreeView> = analyze_forest_with(&trees, &rules);
+        println!("{} trees are visible and the highest scenic score is {}, {}",
+            views.iter().filter(|(_, each)| each.visible).count(),
+            views.iter().map(|(_, each)| each.scenic_score()).max().unwrap_or(0), description);
+    }
+    let views: Grid<TreeView> = analyze_forest(&trees);
+    if trees.is_empty() {
+        println!("There are no trees to look at");
+    }else {
+        let peaks: usize = trees.positions()
+            .filter(|each| trees.neighbors8(*each).all(|other| trees[other] < trees[*each]))
+            .count();
+        println!("{} trees are taller than all of their neighbours", peaks);
+        let columns = (0..views.width()).map(|x| (x, views.column(x).filter(|each| each.visible).count()));
+        if let Some((x, count)) = columns.max_by_key(|(_, count)| *count) {
+            println!("Column {} has the most visible trees, {} of them", x, count);
+        }
+    }
+    // set DAY08_HEATMAPS to a directory to get heatmaps of the forest as well
+    if let Some(directory) = env::var_os("DAY08_HEATMAPS") {
+        match write_heatmaps(&views, Path::new(&directory)) {
+            Ok(()) => println!("Heatmaps written to {}", Path::new(&directory).display()),
+            Err(error) => eprintln!("Couldn't write the heatmaps: {}", error),
+        }
+        println!("Scenic scores:");
+        for each_line in ascii_preview(&views, Layer::ScenicScore) {
+            println!("{}", each_line);
+        }
+    }
+    println!("");
 }
\ No newline at end of file
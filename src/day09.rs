@@ -1,6 +1,8 @@
 use std::fs;
+use std::fmt;
 use std::collections::HashSet;
 use std::str::FromStr;
+use crate::grid::Grid;
 
 fn read_lines(filename: &str) -> Vec<String> {
     /*
@@ -16,156 +18,319 @@ fn read_lines(filename: &str) -> Vec<String> {
     lines
 }
 
-fn ascending_order(a: i32, b: i32) -> (i32, i32) {
-    let results: (i32, i32);
-    if a > b {
-        results = (b, a);
+pub fn touching<const D: usize>(first: [i32; D], second: [i32; D]) -> bool {
+    first.iter().zip(second.iter()).all(|(a, b)| (a - b).abs() <= 1)
+}
+
+pub fn follow<const D: usize>(leader: [i32; D], knot: [i32; D]) -> [i32; D] {
+    /*
+    Where a knot ends up after the knot in front of it moves. If they're no
+    longer touching (including diagonally, along any number of axes), the knot
+    takes one step towards the leader along every axis they differ in.
+    */
+    if touching(leader, knot) {
+        knot
     }else {
-        results = (a, b);
+        std::array::from_fn(|axis| knot[axis] + (leader[axis] - knot[axis]).signum())
     }
-    results
 }
 
-fn calculate_distance(head_x_pos: i32, head_y_pos: i32, tail_x_pos: i32, tail_y_pos: i32) -> i32 {
-    let distance: i32;
-    let (a, b) = ascending_order(head_x_pos, tail_x_pos);
-    let (c, d) = ascending_order(head_y_pos, tail_y_pos);
-    if (b - a).abs() <= 1 && (d - c).abs() <= 1 {
-        distance = 0;
-    }else {
-        distance = 2;
+// the letters making up a direction, along with the axis they move along and
+// which way. y grows downwards, the same way the examples are drawn, and z
+// grows backwards.
+const AXIS_LETTERS: [(char, usize, i32); 6] = [
+    ('L', 0, -1), ('R', 0, 1),
+    ('U', 1, -1), ('D', 1, 1),
+    ('F', 2, -1), ('B', 2, 1),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    // anything that isn't a direction and a distance separated by a space
+    Malformed { line: usize, text: String },
+    UnknownDirection { line: usize, direction: String, dimensions: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Malformed { line, text } =>
+                write!(f, "line {}: expected a direction and a distance, found '{}'", line, text),
+            ParseError::UnknownDirection { line, direction, dimensions } =>
+                write!(f, "line {}: '{}' isn't a direction in {} dimensions", line, direction, dimensions),
+        }
     }
-    //distance = (tail_x_pos - head_x_pos).pow(2) + (tail_y_pos - head_y_pos).pow(2);
-    //distance = (distance as f32).sqrt() as i32;
-    distance
 }
 
-fn move_tail(head_x_pos: i32, head_y_pos: i32, tail_x_pos: i32, tail_y_pos: i32) -> (i32, i32, i32, i32) {
-    let mut results = (head_x_pos, head_y_pos, tail_x_pos, tail_y_pos);
-    if calculate_distance(head_x_pos, head_y_pos, tail_x_pos, tail_y_pos) >= 2 {
-        // move diagonally
-        if head_x_pos > tail_x_pos {
-            results.2 += 1;
-        }else if head_x_pos < tail_x_pos {
-            results.2 -= 1;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Motion<const D: usize> {
+    // one step of the head, at most 1 along each axis
+    pub offset: [i32; D],
+    pub distance: u32,
+}
+
+fn parse_direction<const D: usize>(text: &str) -> Option<[i32; D]> {
+    /*
+    Turn a direction like "U", "DR" or "ULF" into a single step. Each letter
+    moves along a different axis, so "UD" or "LL" aren't directions, and
+    neither is "F" unless there are at least 3 dimensions.
+    */
+    let mut offset: [i32; D] = [0; D];
+    if text.is_empty() {
+        return None;
+    }
+    for letter in text.chars() {
+        let (_, axis, sign) = AXIS_LETTERS.iter().find(|(each, _, _)| *each == letter)?;
+        if *axis >= D || offset[*axis] != 0 {
+            return None;
         }
-        if head_y_pos > tail_y_pos {
-            results.3 += 1;
-        }else if head_y_pos < tail_y_pos {
-            results.3 -= 1;
+        offset[*axis] = *sign;
+    }
+    Some(offset)
+}
+
+fn parse_motion<const D: usize>(line_number: usize, line: &str) -> Result<Motion<D>, ParseError> {
+    /*
+    Read a line like "R 4" or "UL 2".
+    */
+    let malformed = || ParseError::Malformed { line: line_number, text: line.to_string() };
+    let (direction_text, distance_text) = line.split_once(" ").ok_or_else(malformed)?;
+    let distance: u32 = u32::from_str(distance_text).map_err(|_| malformed())?;
+    let offset: [i32; D] = parse_direction(direction_text).ok_or_else(|| ParseError::UnknownDirection {
+        line: line_number,
+        direction: direction_text.to_string(),
+        dimensions: D,
+    })?;
+    Ok(Motion { offset, distance })
+}
+
+pub fn parse_motions<const D: usize>(lines: &[String]) -> Result<Vec<Motion<D>>, ParseError> {
+    lines.iter().enumerate()
+        .filter(|(_, each)| !each.is_empty())
+        .map(|(index, each)| parse_motion(index + 1, each))
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct Rope<const D: usize> {
+    // the head first, then each knot following the one in front of it
+    knots: Vec<[i32; D]>,
+    // every position each knot has been in, starting with the origin
+    visited: Vec<HashSet<[i32; D]>>,
+}
+
+impl<const D: usize> Rope<D> {
+    pub fn new(knot_count: usize) -> Rope<D> {
+        assert!(knot_count > 0, "a rope needs at least one knot");
+        Rope {
+            knots: vec![[0; D]; knot_count],
+            visited: vec![HashSet::from([[0; D]]); knot_count],
         }
     }
-    results
+
+    pub fn knots(&self) -> &[[i32; D]] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> [i32; D] {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> [i32; D] {
+        self.knots[self.knots.len() - 1]
+    }
+
+    pub fn visited(&self, knot_index: usize) -> &HashSet<[i32; D]> {
+        &self.visited[knot_index]
+    }
+
+    pub fn tail_visited(&self) -> &HashSet<[i32; D]> {
+        &self.visited[self.knots.len() - 1]
+    }
+
+    pub fn step(&mut self, offset: [i32; D]) -> &[[i32; D]] {
+        /*
+        Move the head by offset, which can't be more than 1 along any axis,
+        then let each knot catch up with the one in front of it. Returns the
+        positions of every knot afterwards.
+        */
+        assert!(offset.iter().all(|each| each.abs() <= 1), "the head can only move one square at a time");
+        self.knots[0] = std::array::from_fn(|axis| self.knots[0][axis] + offset[axis]);
+        self.visited[0].insert(self.knots[0]);
+        for knot_index in 1..self.knots.len() {
+            self.knots[knot_index] = follow(self.knots[knot_index - 1], self.knots[knot_index]);
+            self.visited[knot_index].insert(self.knots[knot_index]);
+        }
+        &self.knots
+    }
+
+    pub fn apply(&mut self, motion: &Motion<D>) -> Vec<Vec<[i32; D]>> {
+        /*
+        Make every step of a motion, returning the positions of every knot
+        after each one.
+        */
+        (0..motion.distance).map(|_| self.step(motion.offset).to_vec()).collect()
+    }
+
+    pub fn run(&mut self, motions: &[Motion<D>]) -> Vec<Vec<[i32; D]>> {
+        /*
+        The whole trajectory of the rope: where every knot is before the first
+        step and after each one.
+        */
+        let mut trajectory: Vec<Vec<[i32; D]>> = vec![self.knots.clone()];
+        for each in motions {
+            trajectory.extend(self.apply(each));
+        }
+        trajectory
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl Bounds {
+    pub fn of_trajectory(trajectory: &[Vec<[i32; 2]>]) -> Bounds {
+        /*
+        The smallest box holding the start and every position any knot passes
+        through.
+        */
+        let mut bounds: Bounds = Bounds { min_x: 0, min_y: 0, max_x: 0, max_y: 0 };
+        for [x, y] in trajectory.iter().flatten() {
+            bounds.min_x = bounds.min_x.min(*x);
+            bounds.min_y = bounds.min_y.min(*y);
+            bounds.max_x = bounds.max_x.max(*x);
+            bounds.max_y = bounds.max_y.max(*y);
+        }
+        bounds
+    }
+
+    fn blank_canvas(&self) -> Grid<char> {
+        Grid::new((self.max_x - self.min_x + 1) as usize, (self.max_y - self.min_y + 1) as usize, '.')
+    }
+
+    fn canvas_position(&self, position: [i32; 2]) -> (usize, usize) {
+        ((position[0] - self.min_x) as usize, (position[1] - self.min_y) as usize)
+    }
 }
 
-fn process_lines(lines: &Vec<String>) -> i32 {
+fn knot_label(knot_index: usize, knot_count: usize) -> char {
     /*
-    Counts the number of positions the tail visits at least once.
+    'H' for the head and 'T' for the tail of a two-knot rope, the same as
+    the puzzle. Longer ropes number their knots instead, and any past 9 are
+    drawn as '*'.
+    */
+    match knot_index {
+        0 => 'H',
+        1 if knot_count == 2 => 'T',
+        1..=9 => char::from_digit(knot_index as u32, 10).unwrap(),
+        _ => '*',
+    }
+}
 
-    See Part 1 of https://adventofcode.com/2022/day/9
+pub fn render_frame(knots: &[[i32; 2]], bounds: &Bounds, visited: Option<&HashSet<[i32; 2]>>) -> Grid<char> {
+    /*
+    Draw the rope inside the bounds, with the squares in visited (if any)
+    marked '#' and the start marked 's'. Knots nearer the head are drawn on
+    top of the ones behind them, which are drawn on top of the start.
+    */
+    let mut canvas: Grid<char> = bounds.blank_canvas();
+    for each in visited.into_iter().flatten() {
+        canvas[bounds.canvas_position(*each)] = '#';
+    }
+    canvas[bounds.canvas_position([0, 0])] = 's';
+    for (knot_index, each) in knots.iter().enumerate().rev() {
+        canvas[bounds.canvas_position(*each)] = knot_label(knot_index, knots.len());
+    }
+    canvas
+}
+
+pub fn render_visited(visited: &HashSet<[i32; 2]>, bounds: &Bounds) -> Grid<char> {
+    /*
+    The squares in visited marked '#', with the start marked 's'.
+    */
+    render_frame(&[], bounds, Some(visited))
+}
+
+pub fn animate(lines: &[String], knot_count: usize, show_visited: bool) -> Result<(Vec<Grid<char>>, Grid<char>), ParseError> {
+    /*
+    Draw the rope before the first step and after each one, all at the size
+    of the whole trajectory, followed by the map of the squares the tail
+    visited. With show_visited, each frame also marks the squares the tail
+    has visited so far.
     */
-    let mut visited_positions: HashSet<(i32, i32)> = HashSet::new();
-    let mut head_x_pos: i32 = 0;
-    let mut head_y_pos: i32 = 0;
-    let mut tail_x_pos: i32 = 0;
-    let mut tail_y_pos: i32 = 0;
-    for each_instruction in lines {
-        let direction = each_instruction.split(" ").next().unwrap();
-        let distance = i32::from_str(each_instruction.split(" ").skip(1).next().unwrap()).unwrap();
-        //println!("{} {}", direction, distance);
-        for _move_index in 0..distance {
-            match direction {
-                "U" => head_y_pos -= 1,
-                "D" => head_y_pos += 1,
-                "L" => head_x_pos -= 1,
-                "R" => head_x_pos += 1,
-                _ => println!("Unrecognized direction {}", direction),
-            };
-
-            //println!("New head position ({}, {})", head_x_pos, head_y_pos);
-
-            // tail needs to catch up with head
-            (head_x_pos, head_y_pos, tail_x_pos, tail_y_pos) = move_tail(head_x_pos, head_y_pos, tail_x_pos, tail_y_pos);
-
-            //println!("New tail position ({}, {})", tail_x_pos, tail_y_pos);
-
-            // mark tail's current position as visited
-            visited_positions.insert((tail_x_pos, tail_y_pos));
+    let mut rope: Rope<2> = Rope::new(knot_count);
+    let trajectory: Vec<Vec<[i32; 2]>> = rope.run(&parse_motions(lines)?);
+    let bounds: Bounds = Bounds::of_trajectory(&trajectory);
+    let mut tail_visited: HashSet<[i32; 2]> = HashSet::new();
+    let frames: Vec<Grid<char>> = trajectory.iter().map(|knots| {
+        tail_visited.insert(knots[knots.len() - 1]);
+        render_frame(knots, &bounds, if show_visited { Some(&tail_visited) } else { None })
+    }).collect();
+    Ok((frames, render_visited(rope.tail_visited(), &bounds)))
+}
+
+fn simulate<const D: usize>(lines: &[String], knot_count: usize) -> Result<Rope<D>, ParseError> {
+    let mut rope: Rope<D> = Rope::new(knot_count);
+    for each in parse_motions(lines)? {
+        for _ in 0..each.distance {
+            rope.step(each.offset);
         }
     }
+    Ok(rope)
+}
+
+fn process_lines(lines: &[String]) -> Result<i32, ParseError> {
+    /*
+    Counts the number of positions the tail visits at least once.
 
-    visited_positions.len().try_into().unwrap()
+    See Part 1 of https://adventofcode.com/2022/day/9
+    */
+    let rope: Rope<2> = simulate(lines, 2)?;
+    Ok(rope.tail_visited().len() as i32)
 }
 
-fn process_lines2(lines: &Vec<String>) -> i32 {
+fn process_lines2(lines: &[String]) -> Result<i32, ParseError> {
     /*
     Counts the number of positions the tail visits at least once. The twist for
-    Part 2, though, is that the rope has 1 head and 8 tails now, each tail 
+    Part 2, though, is that the rope has 1 head and 9 tails now, each tail 
     acting as the head for the tail behind it. We're only counting the visited
     positions of the final tail.
 
     See Part 2 of https://adventofcode.com/2022/day/9
     */
-    let mut visited_positions: HashSet<(i32, i32)> = HashSet::new();
-    let mut tails: Vec<(i32, i32)> = Vec::new();
-    let mut head_x_pos: i32 = 0;
-    let mut head_y_pos: i32 = 0;
-
-    //initialize tail positions
-    for _tail_index in 0..9 {
-        tails.push((0, 0));
-    }
-
-    // process the moves
-    for each_instruction in lines {
-        let direction = each_instruction.split(" ").next().unwrap();
-        let distance = i32::from_str(each_instruction.split(" ").skip(1).next().unwrap()).unwrap();
-        //println!("{} {}", direction, distance);
-        for _move_index in 0..distance {
-            match direction {
-                "U" => head_y_pos -= 1,
-                "D" => head_y_pos += 1,
-                "L" => head_x_pos -= 1,
-                "R" => head_x_pos += 1,
-                _ => println!("Unrecognized direction {}", direction),
-            };
-
-            //println!("New head position ({}, {})", head_x_pos, head_y_pos);
-
-            // each tail needs to catch up with the head/tail in front of it
-            let mut previous_tail_x = head_x_pos;
-            let mut previous_tail_y = head_y_pos;
-            for tail_index in 0..tails.len() {
-                (_, _, tails[tail_index].0, tails[tail_index].1) = move_tail(previous_tail_x, previous_tail_y, tails[tail_index].0, tails[tail_index].1);
-                previous_tail_x = tails[tail_index].0;
-                previous_tail_y = tails[tail_index].1;
-                //println!("New tail {} position ({}, {})", tail_index, previous_tail_x, previous_tail_y);
-            }
-            
-            //println!("New final tail position ({}, {})", tails[8].0, tails[8].1);
-
-            // mark tail's current position as visited
-            visited_positions.insert((tails[8].0, tails[8].1));
-        }
-    }
-
-    visited_positions.len().try_into().unwrap()
+    let rope: Rope<2> = simulate(lines, 10)?;
+    Ok(rope.tail_visited().len() as i32)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // the original two-dimensional forms of touching() and follow()
+    fn calculate_distance(head_x_pos: i32, head_y_pos: i32, tail_x_pos: i32, tail_y_pos: i32) -> i32 {
+        if touching([head_x_pos, head_y_pos], [tail_x_pos, tail_y_pos]) { 0 } else { 2 }
+    }
+
+    fn move_tail(head_x_pos: i32, head_y_pos: i32, tail_x_pos: i32, tail_y_pos: i32) -> (i32, i32, i32, i32) {
+        let [new_x, new_y] = follow([head_x_pos, head_y_pos], [tail_x_pos, tail_y_pos]);
+        (head_x_pos, head_y_pos, new_x, new_y)
+    }
+
     #[test]
     fn test_process_lines_short() {
         let lines = read_lines("day09_input_short.txt");
-        assert_eq!(process_lines(&lines), 13);
+        assert_eq!(process_lines(&lines), Ok(13));
     }
 
     #[test]
     fn test_process_lines_full() {
         let lines = read_lines("day09_input.txt");
-        assert_eq!(process_lines(&lines), 6470);
+        assert_eq!(process_lines(&lines), Ok(6470));
     }
 
     #[test]
@@ -220,45 +385,194 @@ mod tests {
 
     #[test]
     fn test_process_lines_01() {
-        assert_eq!(process_lines(&vec!["R 4".to_string(), "U 4".to_string()]), 7);
+        assert_eq!(process_lines(&["R 4".to_string(), "U 4".to_string()]), Ok(7));
     }
 
     #[test]
     fn test_process_lines2_short() {
         let lines = read_lines("day09_input_short2.txt");
-        assert_eq!(process_lines2(&lines), 36);
+        assert_eq!(process_lines2(&lines), Ok(36));
     }
 
     #[test]
     fn test_process_lines2_full() {
         let lines = read_lines("day09_input.txt");
-        assert_eq!(process_lines2(&lines), 2658);
+        assert_eq!(process_lines2(&lines), Ok(2658));
     }
 
     #[test]
     fn test_process_lines2_01() {
-        assert_eq!(process_lines2(&vec!["R 4".to_string()]), 1);
+        assert_eq!(process_lines2(&["R 4".to_string()]), Ok(1));
     }
 
     #[test]
     fn test_process_lines2_02() {
-        assert_eq!(process_lines2(&vec!["R 10".to_string()]), 2);
+        assert_eq!(process_lines2(&["R 10".to_string()]), Ok(2));
     }
 
     #[test]
     fn test_process_lines2_03() {
-        assert_eq!(process_lines2(&vec!["R 5".to_string(), "U 8".to_string()]), 1);
+        assert_eq!(process_lines2(&["R 5".to_string(), "U 8".to_string()]), Ok(1));
     }
 
     #[test]
     fn test_process_lines2_04() {
-        assert_eq!(process_lines2(&vec!["R 5".to_string(), "U 8".to_string(), "L 8".to_string()]), 4);
+        assert_eq!(process_lines2(&["R 5".to_string(), "U 8".to_string(), "L 8".to_string()]), Ok(4));
+    }
+
+    #[test]
+    fn test_rope_step() {
+        let mut rope: Rope<2> = Rope::new(3);
+        assert_eq!(rope.step([1, 0]), [[1, 0], [0, 0], [0, 0]]);
+        assert_eq!(rope.step([1, 0]), [[2, 0], [1, 0], [0, 0]]);
+        assert_eq!(rope.step([0, -1]), [[2, -1], [1, 0], [0, 0]]);
+        assert_eq!(rope.step([0, -1]), [[2, -2], [2, -1], [1, -1]]);
+        assert_eq!(rope.head(), [2, -2]);
+        assert_eq!(rope.tail(), [1, -1]);
+        assert_eq!(rope.visited(1).len(), 3);
+        assert_eq!(rope.tail_visited(), &HashSet::from([[0, 0], [1, -1]]));
+    }
+
+    #[test]
+    fn test_rope_run() {
+        let motions: Vec<Motion<2>> = parse_motions(&["R 4".to_string(), "U 2".to_string()]).unwrap();
+        let mut rope: Rope<2> = Rope::new(2);
+        let trajectory: Vec<Vec<[i32; 2]>> = rope.run(&motions);
+        // the starting position, then one frame per step
+        assert_eq!(trajectory.len(), 7);
+        assert_eq!(trajectory[0], vec![[0, 0], [0, 0]]);
+        assert_eq!(trajectory[4], vec![[4, 0], [3, 0]]);
+        assert_eq!(trajectory[6], vec![[4, -2], [4, -1]]);
+        assert_eq!(rope.knots(), [[4, -2], [4, -1]]);
+    }
+
+    #[test]
+    fn test_visited_by_every_knot() {
+        // the knot right behind the head of a long rope follows the same path
+        // as the tail of a short one
+        let lines = read_lines("day09_input_short.txt");
+        let rope: Rope<2> = simulate(&lines, 10).unwrap();
+        assert_eq!(rope.visited(1).len(), 13);
+        assert_eq!(rope.visited(9).len(), 1);
+        let short_rope: Rope<2> = simulate(&lines, 2).unwrap();
+        assert_eq!(rope.visited(0), short_rope.visited(0));
+        // a rope of one knot is just the head
+        let single_knot: Rope<2> = simulate(&lines, 1).unwrap();
+        assert_eq!(single_knot.tail_visited().len(), rope.visited(0).len());
+    }
+
+    #[test]
+    fn test_parse_motions() {
+        let lines: Vec<String> = vec!["R 4".to_string(), "".to_string(), "UL 10".to_string(), "DR 1".to_string()];
+        assert_eq!(parse_motions(&lines), Ok(vec![Motion { offset: [1, 0], distance: 4 },
+                                                  Motion { offset: [-1, -1], distance: 10 },
+                                                  Motion { offset: [1, 1], distance: 1 }]));
+        let lines: Vec<String> = vec!["F 2".to_string(), "DLB 1".to_string(), "RU 3".to_string()];
+        assert_eq!(parse_motions(&lines), Ok(vec![Motion { offset: [0, 0, -1], distance: 2 },
+                                                  Motion { offset: [-1, 1, 1], distance: 1 },
+                                                  Motion { offset: [1, -1, 0], distance: 3 }]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let lines: Vec<String> = vec!["R 4".to_string(), "X 2".to_string()];
+        let error: ParseError = parse_motions::<2>(&lines).unwrap_err();
+        assert_eq!(error, ParseError::UnknownDirection { line: 2, direction: "X".to_string(), dimensions: 2 });
+        assert_eq!(error.to_string(), "line 2: 'X' isn't a direction in 2 dimensions");
+        // the third axis only exists in 3 dimensions
+        assert!(parse_motions::<2>(&["F 1".to_string()]).is_err());
+        assert!(parse_motions::<3>(&["F 1".to_string()]).is_ok());
+        // each letter has to move along a different axis
+        for each in ["UD 1", "LL 1", "RUR 1"] {
+            assert!(matches!(parse_motions::<3>(&[each.to_string()]), Err(ParseError::UnknownDirection { .. })));
+        }
+        for each in ["R", "R x", "R -1", "R  1"] {
+            assert_eq!(parse_motions::<2>(&[each.to_string()]),
+                Err(ParseError::Malformed { line: 1, text: each.to_string() }));
+        }
+        assert_eq!(parse_motions::<2>(&["R".to_string()]).unwrap_err().to_string(),
+            "line 1: expected a direction and a distance, found 'R'");
+        // the puzzle answers report the error instead of panicking
+        assert_eq!(process_lines(&lines), Err(ParseError::UnknownDirection { line: 2, direction: "X".to_string(), dimensions: 2 }));
+        assert_eq!(process_lines2(&["R".to_string()]), Err(ParseError::Malformed { line: 1, text: "R".to_string() }));
+    }
+
+    #[test]
+    fn test_diagonal_motions() {
+        let lines: Vec<String> = vec!["UL 3".to_string(), "DR 1".to_string()];
+        let rope: Rope<2> = simulate(&lines, 2).unwrap();
+        assert_eq!(rope.knots(), [[-2, -2], [-2, -2]]);
+        assert_eq!(rope.tail_visited(), &HashSet::from([[0, 0], [-1, -1], [-2, -2]]));
+    }
+
+    #[test]
+    fn test_three_dimensions() {
+        let lines: Vec<String> = vec!["F 3".to_string(), "UR 2".to_string()];
+        let rope: Rope<3> = simulate(&lines, 2).unwrap();
+        assert_eq!(rope.head(), [2, -2, -3]);
+        assert_eq!(rope.tail(), [1, -1, -3]);
+        assert_eq!(rope.tail_visited().len(), 4);
+        // a knot pulled along all three axes at once moves along all three
+        assert_eq!(follow([2, 2, 2], [0, 1, 1]), [1, 2, 2]);
+        assert_eq!(follow([2, 2, 2], [1, 1, 1]), [1, 1, 1]);
+        assert!(!touching([0, 0, 0, 0], [1, 1, 1, 2]));
+    }
+
+    #[test]
+    fn test_animate_two_knots() {
+        let lines = read_lines("day09_input_short.txt");
+        let (frames, visited_map) = animate(&lines, 2, false).unwrap();
+        assert_eq!(frames.len(), 25);
+        // H covers T, which covers s
+        assert_eq!(frames[0].to_string(), "......\n......\n......\n......\nH.....");
+        assert_eq!(frames[1].to_string(), "......\n......\n......\n......\nTH....");
+        assert_eq!(frames[8].to_string(), "....H.\n....T.\n......\n......\ns.....");
+        assert_eq!(visited_map.to_string(), "..##..\n...##.\n.####.\n....#.\ns###..");
+        let (frames, _) = animate(&lines, 2, true).unwrap();
+        assert_eq!(frames[8].to_string(), "....H.\n....T.\n....#.\n....#.\ns###..");
+    }
+
+    #[test]
+    fn test_animate_ten_knots() {
+        let lines = read_lines("day09_input_short.txt");
+        let (frames, visited_map) = animate(&lines, 10, false).unwrap();
+        // 4 covers the rest of the rope, and the start
+        assert_eq!(frames[4].to_string(), "......\n......\n......\n......\n4321H.");
+        // 6 covers the rest of the rope, and the start
+        assert_eq!(frames[8].to_string(), "....H.\n....1.\n..432.\n.5....\n6.....");
+        assert_eq!(visited_map.to_string(), "......\n......\n......\n......\ns.....");
+        assert!(animate(&["Z 1".to_string()], 10, false).is_err());
+    }
+
+    #[test]
+    fn test_knot_labels() {
+        let labels: String = (0..12).map(|each| knot_label(each, 12)).collect();
+        assert_eq!(labels, "H123456789**");
+        let bounds = Bounds::of_trajectory(&[vec![[-2, 1], [3, -1]]]);
+        assert_eq!(bounds, Bounds { min_x: -2, min_y: -1, max_x: 3, max_y: 1 });
+        assert_eq!(render_frame(&[[1, 0]], &bounds, None).to_string(), "......\n..sH..\n......");
     }
 }
 
 pub fn main() {
     let result = read_lines("day09_input_short2.txt");
     println!("Day 9:");
-    println!("Part 1 - The number of positions the tail visits at least once is: {}", process_lines(&result));
-    println!("Part 2 - The number of positions the tail visits at least once is: {}", process_lines2(&result));
+    match process_lines(&result) {
+        Ok(count) => println!("Part 1 - The number of positions the tail visits at least once is: {}", count),
+        Err(error) => println!("Part 1 - Invalid input, {}", error),
+    }
+    match process_lines2(&result) {
+        Ok(count) => println!("Part 2 - The number of positions the tail visits at least once is: {}", count),
+        Err(error) => println!("Part 2 - Invalid input, {}", error),
+    }
+    if let Ok(rope) = simulate::<2>(&result, 10) {
+        let counts: Vec<String> = (0..rope.knots().len()).map(|index| rope.visited(index).len().to_string()).collect();
+        println!("From the head to the tail, the knots visit {} positions", counts.join(", "));
+        println!("The head ends up at {:?} and the tail at {:?}", rope.head(), rope.tail());
+    }
+    if let Ok((_, visited_map)) = animate(&result, 10, false) {
+        println!("The positions the tail visits:");
+        println!("{}", visited_map);
+    }
+    println!();
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use std::fs;
 use std::str::FromStr;
+use crate::grid::Grid;
 
 fn read_lines(filename: &str) -> Vec<String> {
     /*
@@ -30,7 +31,7 @@ fn process_lines(lines: &Vec<String>) -> i32 {
     for each_instruction in lines {
         if each_instruction != "noop" {
             let instruction = each_instruction.split(" ").next().unwrap();
-            let value = i32::from_str(each_instruction.split(" ").skip(1).next().unwrap()).unwrap();
+            let value = i32::from_str(each_instruction.split(" ").nth(1).unwrap()).unwrap();
             if instruction != "addx" {
                 eprintln!("Unrecognized instruction {}", instruction);
                 break;
@@ -61,65 +62,54 @@ fn process_lines(lines: &Vec<String>) -> i32 {
     recordings.into_iter().reduce(|x, y| x + y).unwrap()
 }
 
-fn draw_screen(pixels: Vec<char>) -> Vec<String> {
-    let mut output: Vec<String> = Vec::new();
-    for each_line in pixels.chunks(40) {
-        output.push(each_line.into_iter().collect::<String>());
+fn draw_screen(pixels: &Grid<char>) -> Vec<String> {
+    pixels.rows().map(|each_line| each_line.iter().collect::<String>()).collect()
+}
+
+fn draw_pixel(pixels: &mut Grid<char>, cycle: i32, register_x: i32) {
+    /*
+    The CRT draws one pixel per cycle, left to right and top to bottom. The
+    pixel is lit if the 3-pixel-wide sprite centred on register_x covers it.
+    */
+    let position: (usize, usize) = ((cycle % 40) as usize, (cycle / 40) as usize);
+    if let Some(pixel) = pixels.get_mut(position) {
+        *pixel = if ((cycle % 40) - register_x).abs() <= 1 { '#' } else { '.' };
     }
-    output
 }
 
-fn process_lines2(lines: &Vec<String>) -> Vec<String> {
+fn process_lines2(lines: &[String]) -> Vec<String> {
     /*
+    Emulate the communicator CPU again, this time drawing the 40x6 CRT screen
+    as it goes, and return the rows of the screen.
 
     See Part 2 of https://adventofcode.com/2022/day/10
     */
     let mut cycle: i32 = 0;
     let mut register_x: i32 = 1;
-    let mut pixels: Vec<char> = Vec::new();
     // initialize the display
-    for _ in 0..240 {
-        pixels.push('.');
-    }
+    let mut pixels: Grid<char> = Grid::new(40, 6, '.');
     // process the instructions
     for each_instruction in lines {
-        if (cycle % 40) == register_x - 1 || (cycle % 40) == register_x || (cycle % 40) == register_x + 1 {
-            //println!("Storing signal {} at cycle {}", register_x, cycle);
-            pixels[cycle as usize] = '#';
-        }else {
-            pixels[cycle as usize] = '.';
-        }
+        draw_pixel(&mut pixels, cycle, register_x);
 
         if each_instruction != "noop" {
             let instruction = each_instruction.split(" ").next().unwrap();
-            let value = i32::from_str(each_instruction.split(" ").skip(1).next().unwrap()).unwrap();
-            //println!("Cycle {}, register_x {}: {} {}", cycle, register_x, instruction, value);
+            let value = i32::from_str(each_instruction.split(" ").nth(1).unwrap()).unwrap();
             if instruction != "addx" {
                 eprintln!("Unrecognized instruction {}", instruction);
                 break;
             }
             cycle += 1;
-            if (cycle % 40) == register_x - 1 || (cycle % 40) == register_x || (cycle % 40) == register_x + 1 {
-                //println!("Storing signal {} at cycle {}", register_x, cycle);
-                pixels[cycle as usize] = '#';
-            }else {
-                pixels[cycle as usize] = '.';
-            }
+            draw_pixel(&mut pixels, cycle, register_x);
             cycle += 1;
             register_x += value;
-            if (cycle % 40) == register_x - 1 || (cycle % 40) == register_x || (cycle % 40) == register_x + 1 {
-                //println!("Storing signal {} at cycle {}", register_x, cycle);
-                pixels[cycle as usize] = '#';
-            }else {
-                pixels[cycle as usize] = '.';
-            }
+            draw_pixel(&mut pixels, cycle, register_x);
         }else {
-            //println!("Checking if cycle {} is in the list.", cycle);
             cycle += 1;
         }
     }
 
-    draw_screen(pixels)
+    draw_screen(&pixels)
 }
 
 #[cfg(test)]
@@ -171,4 +161,5 @@ pub fn main() {
     for each_line in process_lines2(&result) {
         println!("{}", each_line);
     }
+    println!();
 }
\ No newline at end of file
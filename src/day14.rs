@@ -1,40 +1,27 @@
 use std::fs;
+use std::fmt;
 use std::str::FromStr;
 use std::mem::swap;
-use std::cmp::Ordering;
+use std::cmp::{max, Ordering};
 use regex::Regex;
-use std::collections::HashMap;
+use crate::grid::Grid;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 enum CaveEnum {
+    Air,
     Rock,
     Sand,
 }
 
-fn _cave_hashmap_to_string(hashmap: &HashMap<(usize, usize), CaveEnum>) -> String {
-    let mut result: String = "".to_string();
-    let mut point_map: Vec<String> = vec![];
-    for y in 0..12 {
-        let mut line: String = "".to_string();
-        for x in 486..512 {
-            let c: char = match hashmap.get(&(x, y)) {
-                Some(x) => {
-                    match x {
-                        CaveEnum::Rock => '#',
-                        CaveEnum::Sand => 'o',
-                    }
-                },
-                None => '.',
-            };
-            line.push(c);
-        }
-        line.push('\n');
-        point_map.push(line);
+impl fmt::Display for CaveEnum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result: char = match self {
+            CaveEnum::Air => '.',
+            CaveEnum::Rock => '#',
+            CaveEnum::Sand => 'o',
+        };
+        write!(f, "{}", result)
     }
-    for each in point_map {
-        result = result + &each;
-    }
-    result
 }
 
 fn read_lines(filename: &str) -> Vec<String> {
@@ -63,18 +50,17 @@ enum Direction {
     Right,
 }
 
-fn draw_lines(line: String, hashmap: HashMap<(usize, usize), CaveEnum>) -> HashMap<(usize, usize), CaveEnum> {
+fn draw_lines(line: String, cave: &mut Grid<CaveEnum>) {
     /*
     Takes an input of 2 or more points (represented as x-y coordinates 
     separated by commas), with points separated by "->" arrows.
 
     For example, 2,3 -> 2, 5 -> 5,5 represents two line segments
 
-    Populates all of the points in those line segments on the hashmap provided
+    Populates all of the points in those line segments on the cave provided
     as CaveEnum::Rock.
     */
     let points: Vec<String> = line.split(" -> ").map(|s| s.to_string()).collect();
-    let mut result_hashmap = hashmap.clone();
     for index in 0..points.len()-1 {
         let mut start: (i32, i32) = parse_point(points[index].clone());
         let mut end: (i32, i32) = parse_point(points[index+1].clone());
@@ -96,7 +82,7 @@ fn draw_lines(line: String, hashmap: HashMap<(usize, usize), CaveEnum>) -> HashM
                         swap(&mut start, &mut end);
                     },
                     Ordering::Equal => {
-                        continue;
+                        // a single point, which is still a rock
                     }
                 }
             }
@@ -104,115 +90,117 @@ fn draw_lines(line: String, hashmap: HashMap<(usize, usize), CaveEnum>) -> HashM
         match direction {
             Direction::Down => {
                 for y in start.1..end.1+1 {
-                    //println!("Adding point ({}, {})", start.0, y);
-                    result_hashmap.insert((start.0 as usize, y as usize), CaveEnum::Rock);
+                    cave[(start.0 as usize, y as usize)] = CaveEnum::Rock;
                 }
             },
             Direction::Right => {
                 for x in start.0..end.0+1 {
-                    //println!("Adding point ({}, {})", x, start.1);
-                    result_hashmap.insert((x as usize, start.1 as usize), CaveEnum::Rock);
+                    cave[(x as usize, start.1 as usize)] = CaveEnum::Rock;
                 }
             },
         }
     }
-    result_hashmap
 }
 
-fn next_point_below(falling_sand: (usize, usize), hashmap: &HashMap<(usize, usize), CaveEnum>) -> ((usize, usize), bool) {
-    let mut found = false;
-    let mut highest_point: (usize, usize) = falling_sand;
-
-    for each_point in hashmap.keys() {
-        if each_point.0 == falling_sand.0 && each_point.1 > falling_sand.1 {
-            if !found || each_point.1 < highest_point.1 {
-                highest_point.0 = each_point.0;
-                highest_point.1 = each_point.1;
-                found = true;
-            }
-        }
+fn next_point_below(falling_sand: (usize, usize), cave: &Grid<CaveEnum>) -> ((usize, usize), bool) {
+    /*
+    Find the first rock or sand straight below the falling sand, if any.
+    */
+    match cave.ray(falling_sand, (0, 1)).find(|each| cave[*each] != CaveEnum::Air) {
+        Some(point) => (point, true),
+        None => (falling_sand, false),
     }
-    (highest_point, found)
 }
 
-fn get_maximum_height(hashmap: &HashMap<(usize, usize), CaveEnum>) -> usize {
-    let mut heighest_point: (usize, usize) = *hashmap.keys().last().unwrap();
-
-    for each_point in hashmap.keys() {
-        if each_point.1 > heighest_point.1 {
-            heighest_point = (each_point.0, each_point.1);
+fn get_maximum_point(lines: &[String]) -> (usize, usize) {
+    /*
+    The largest x and y of any point in the rock lines.
+    */
+    let mut maximum: (usize, usize) = (0, 0);
+    for each_line in lines {
+        for each_point in each_line.split(" -> ") {
+            let point: (i32, i32) = parse_point(each_point.to_string());
+            maximum = (max(maximum.0, point.0 as usize), max(maximum.1, point.1 as usize));
         }
     }
-
-    heighest_point.1
+    maximum
 }
 
-fn process_lines(lines: &Vec<String>) -> i32 {
+fn build_cave(lines: &[String], with_floor: bool) -> Grid<CaveEnum> {
     /*
-
-    See Part 1 of https://adventofcode.com/2022/day/14
+    Draw the rocks into a cave that's big enough to hold all the sand. With a
+    floor, two rows below the lowest rock, sand can pile up at most as far to
+    either side of (500, 0) as the floor is deep, so the cave is made wide
+    enough for that. Without one, sand that leaves the cave is in the abyss.
     */
-    let mut hashmap: HashMap<(usize, usize), CaveEnum> = HashMap::<(usize, usize), CaveEnum>::new();
-    let mut units_sand_rested: i32 = 0;
-    let mut falling_sand: (usize, usize);
-    let mut abyss_reached: bool = false;
-    
-    // parse the input lines to find the walls of the cave
+    let maximum: (usize, usize) = get_maximum_point(lines);
+    let floor_height: usize = maximum.1 + 2;
+    let (width, height) = if with_floor {
+        (max(maximum.0, 500 + floor_height) + 2, floor_height + 1)
+    }else {
+        (max(maximum.0, 500) + 2, maximum.1 + 1)
+    };
+    let mut cave: Grid<CaveEnum> = Grid::new(width, height, CaveEnum::Air);
     for each_line in lines {
-        hashmap = draw_lines(each_line.clone(), hashmap);
+        draw_lines(each_line.clone(), &mut cave);
+    }
+    if with_floor {
+        for x in 0..width {
+            cave[(x, floor_height)] = CaveEnum::Rock;
+        }
     }
+    cave
+}
 
-    // simulate sand falling until one lands in the abyss
-    while !abyss_reached {
-        // each new grain of sand starts at (500, 0)
-        let mut found_rest: bool = false;
-        falling_sand = (500, 0);
-        //println!("Dropping {}th grain of sand", units_sand_rested);
-        //println!("{}", _cave_hashmap_to_string(&hashmap));
-        //println!("Total solid particles on on map {}", hashmap.len());
-    
-        while !found_rest {
-            // see if the sand falls into the abyss
-            let (next_point, found) = next_point_below(falling_sand, &hashmap);
-            //println!("Next spot to land on ({},{})", next_point.0, next_point.1);
+fn pour_sand(cave: &mut Grid<CaveEnum>) -> i32 {
+    /*
+    Drop grains of sand from (500, 0) one at a time, until either a grain falls
+    out of the cave or the source itself is covered, and return how many came
+    to rest.
+    */
+    let mut units_sand_rested: i32 = 0;
+    while cave.get((500, 0)) == Some(&CaveEnum::Air) {
+        let mut falling_sand: (usize, usize) = (500, 0);
+        loop {
+            // fall straight down as far as possible
+            let (next_point, found) = next_point_below(falling_sand, cave);
             if !found {
-                abyss_reached = true;
-                break;
-            }
-            if next_point == (500, 0) {
-                panic!("Sand backed up to the top, which shouldn't be possible.");
+                return units_sand_rested;
             }
-
-            // the sand lands on top of the next point found
-            falling_sand.0 = next_point.0; 
-            falling_sand.1 = next_point.1 - 1;
-
-            // look for an opening to the lower left or lower right
-            if !hashmap.contains_key(&(falling_sand.0 - 1, falling_sand.1 + 1)) {
-                falling_sand.0 -= 1;
-                falling_sand.1 += 1;
-                //println!("Grain of sand slid down-left to ({}, {})", falling_sand.0, falling_sand.1);
-            }else if !hashmap.contains_key(&(falling_sand.0 + 1, falling_sand.1 + 1)) {
-                falling_sand.0 += 1;
-                falling_sand.1 += 1;
-                //println!("Grain of sand slid down-right to ({}, {})", falling_sand.0, falling_sand.1);
-            }else {
-                // came to rest
-                found_rest = true;
-                units_sand_rested += 1;
+            falling_sand = (next_point.0, next_point.1 - 1);
+
+            // look for an opening to the lower left or lower right, where
+            // anywhere outside the cave counts as an opening
+            let down_left: Option<(usize, usize)> = cave.step(falling_sand, (-1, 1));
+            let down_right: Option<(usize, usize)> = cave.step(falling_sand, (1, 1));
+            match (down_left.map(|each| cave[each]), down_right.map(|each| cave[each])) {
+                (None, _) => return units_sand_rested,
+                (Some(CaveEnum::Air), _) => falling_sand = down_left.unwrap(),
+                (_, None) => return units_sand_rested,
+                (_, Some(CaveEnum::Air)) => falling_sand = down_right.unwrap(),
+                _ => break,
             }
         }
         // register the new grain of sand
-        if !abyss_reached {
-            //println!("Grain of sand rested at ({}, {})", falling_sand.0, falling_sand.1);
-            hashmap.insert(falling_sand, CaveEnum::Sand);
-        }
+        cave[falling_sand] = CaveEnum::Sand;
+        units_sand_rested += 1;
     }
-
     units_sand_rested
 }
 
-fn process_lines2(lines: &Vec<String>) -> i32 {
+fn process_lines(lines: &[String]) -> i32 {
+    /*
+    Simulates sand falling from (500, 0) into a cave of rock lines, returning
+    the number of units of sand that come to rest before sand starts flowing
+    into the abyss below.
+
+    See Part 1 of https://adventofcode.com/2022/day/14
+    */
+    let mut cave: Grid<CaveEnum> = build_cave(lines, false);
+    pour_sand(&mut cave)
+}
+
+fn process_lines2(lines: &[String]) -> i32 {
     /*
     The same as process_lines(), except now we're assuming there's a floor 
     infinitely long, two units below the lowest point in the input. Because y=0
@@ -223,75 +211,8 @@ fn process_lines2(lines: &Vec<String>) -> i32 {
 
     See Part 2 of https://adventofcode.com/2022/day/14
     */
-    let mut hashmap: HashMap<(usize, usize), CaveEnum> = HashMap::<(usize, usize), CaveEnum>::new();
-    let mut units_sand_rested: i32 = 0;
-    let mut falling_sand: (usize, usize);
-    let mut ceiling_blocked: bool = false;
-    let floor_height: usize;
-    
-    // parse the input lines to find the walls of the cave
-    for each_line in lines {
-        hashmap = draw_lines(each_line.clone(), hashmap);
-    }
-
-    floor_height = get_maximum_height(&hashmap) + 2;
-
-    // simulate sand falling until one lands in the abyss
-    while !ceiling_blocked {
-        // each new grain of sand starts at (500, 0)
-        let mut found_rest: bool = false;
-        falling_sand = (500, 0);
-        println!("Dropping {}th grain of sand", units_sand_rested);
-        println!("{}", _cave_hashmap_to_string(&hashmap));
-        //println!("Total solid particles on on map {}", hashmap.len());
-    
-        while !found_rest {
-            // see if the sand falls into the abyss
-            let (next_point, found) = next_point_below(falling_sand, &hashmap);
-            println!("Next spot to land on ({},{})", next_point.0, next_point.1);
-            if !found {
-                falling_sand.1 = floor_height - 1;
-                found_rest = true;
-                break;
-            }
-            if next_point == (500, 0) && hashmap.contains_key(&(500, 1)) {
-                ceiling_blocked = true;
-                break;
-            }
-
-            // the sand lands on top of the next point found
-            falling_sand.0 = next_point.0;
-            falling_sand.1 = next_point.1 - 1;
-
-            // look for an opening to the lower left or lower right
-            if !hashmap.contains_key(&(falling_sand.0 - 1, falling_sand.1 + 1)) {
-                falling_sand.0 -= 1;
-                falling_sand.1 += 1;
-                //println!("Grain of sand slid down-left to ({}, {})", falling_sand.0, falling_sand.1);
-            }else if !hashmap.contains_key(&(falling_sand.0 + 1, falling_sand.1 + 1)) {
-                falling_sand.0 += 1;
-                falling_sand.1 += 1;
-                //println!("Grain of sand slid down-right to ({}, {})", falling_sand.0, falling_sand.1);
-            }else {
-                // came to rest
-                found_rest = true;
-                units_sand_rested += 1;
-            }
-            if units_sand_rested > 79 {
-                ceiling_blocked = true;
-                println!("Units sand rested is {}, but sand particles is {}", units_sand_rested,
-                hashmap.values().filter(|&x| x == &CaveEnum::Sand).collect::<Vec<&CaveEnum>>().len());
-                todo!("Crutch to kill infinite loop.");
-            }
-        }
-        // register the new grain of sand
-        if !ceiling_blocked {
-            //println!("Grain of sand rested at ({}, {})", falling_sand.0, falling_sand.1);
-            hashmap.insert(falling_sand, CaveEnum::Sand);
-        }
-    }
-
-    units_sand_rested
+    let mut cave: Grid<CaveEnum> = build_cave(lines, true);
+    pour_sand(&mut cave)
 }
 
 #[cfg(test)]
@@ -313,13 +234,13 @@ mod tests {
     #[test]
     fn test_process_lines2_short() {
         let lines = read_lines("day14_input_short.txt");
-        assert_eq!(process_lines(&lines), 93);
+        assert_eq!(process_lines2(&lines), 93);
     }
 
     #[test]
     fn test_process_lines2_full() {
         let lines = read_lines("day14_input.txt");
-        assert_eq!(process_lines2(&lines), -1);
+        assert_eq!(process_lines2(&lines), 30157);
     }
 
     #[test]
@@ -382,42 +303,64 @@ mod tests {
         assert_eq!(parse_point("0,5".to_string()), (0, 5));
     }
 
+    fn count_rocks(cave: &Grid<CaveEnum>) -> usize {
+        cave.iter().filter(|(_, each)| **each == CaveEnum::Rock).count()
+    }
+
     #[test]
     fn test_draw_lines_01() {
-        let mut hashmap: HashMap<(usize, usize), CaveEnum> = HashMap::<(usize, usize), CaveEnum>::new();
-        hashmap = draw_lines("500,0 -> 500,10".to_string(), hashmap);
-        println!("Hashmap contains {} points.", hashmap.len());
-        assert_eq!(hashmap.len(), 11);
-        let cell_value: CaveEnum = *hashmap.get(&(500,0)).unwrap();
-        assert_eq!(cell_value, *Some(&CaveEnum::Rock).unwrap());
+        let mut cave: Grid<CaveEnum> = Grid::new(600, 20, CaveEnum::Air);
+        draw_lines("500,0 -> 500,10".to_string(), &mut cave);
+        assert_eq!(count_rocks(&cave), 11);
+        assert_eq!(cave[(500,0)], CaveEnum::Rock);
     }
 
     #[test]
     fn test_draw_lines_02() {
-        let mut hashmap: HashMap<(usize, usize), CaveEnum> = HashMap::<(usize, usize), CaveEnum>::new();
-        hashmap = draw_lines("500,0 -> 500,1 -> 501,1".to_string(), hashmap);
-        assert_eq!(hashmap.len(), 3);
-        let cell_value: CaveEnum = *hashmap.get(&(501,1)).unwrap();
-        assert_eq!(cell_value, *Some(&CaveEnum::Rock).unwrap());
+        let mut cave: Grid<CaveEnum> = Grid::new(600, 20, CaveEnum::Air);
+        draw_lines("500,0 -> 500,1 -> 501,1".to_string(), &mut cave);
+        assert_eq!(count_rocks(&cave), 3);
+        assert_eq!(cave[(501,1)], CaveEnum::Rock);
     }
 
     #[test]
     fn test_draw_lines_03() {
-        let mut hashmap: HashMap<(usize, usize), CaveEnum> = HashMap::<(usize, usize), CaveEnum>::new();
-        hashmap = draw_lines("498,4 -> 498,6 -> 496,6".to_string(), hashmap);
-        assert_eq!(hashmap.len(), 5);
-        let cell_value: CaveEnum = *hashmap.get(&(497,6)).unwrap();
-        assert_eq!(cell_value, *Some(&CaveEnum::Rock).unwrap());
+        let mut cave: Grid<CaveEnum> = Grid::new(600, 20, CaveEnum::Air);
+        draw_lines("498,4 -> 498,6 -> 496,6".to_string(), &mut cave);
+        assert_eq!(count_rocks(&cave), 5);
+        assert_eq!(cave[(497,6)], CaveEnum::Rock);
     }
 
     #[test]
     fn test_next_point_below_01() {
-        let mut hashmap: HashMap<(usize, usize), CaveEnum> = HashMap::<(usize, usize), CaveEnum>::new();
-        hashmap = draw_lines("500,10 -> 509,10".to_string(), hashmap);
-        let (destination, found) = next_point_below((500,0), &hashmap);
+        let mut cave: Grid<CaveEnum> = Grid::new(600, 20, CaveEnum::Air);
+        draw_lines("500,10 -> 509,10".to_string(), &mut cave);
+        let (destination, found) = next_point_below((500,0), &cave);
         assert!(found);
         assert_eq!(destination, (500,10));
     }
+
+    #[test]
+    fn test_cave_display() {
+        // the picture from the puzzle, once the sand starts falling into the abyss
+        let lines = read_lines("day14_input_short.txt");
+        let mut cave = build_cave(&lines, false);
+        assert_eq!(pour_sand(&mut cave), 24);
+        let rows: Vec<String> = cave.to_string().lines().map(|each| each[494..].to_string()).collect();
+        assert_eq!(rows, vec![
+            "...........",
+            "...........",
+            "......o....",
+            ".....ooo...",
+            "....#ooo##.",
+            "...o#ooo#..",
+            "..###ooo#..",
+            "....oooo#..",
+            ".o.ooooo#..",
+            "#########..",
+        ]);
+        assert_eq!(process_lines2(&lines), 93);
+    }
 }
 
 pub fn main() {
@@ -0,0 +1,279 @@
+/*
+A rectangular grid stored row by row, shared by the puzzles that are laid out
+on a map (Days 8, 10, 12 and 14). Positions are (x, y) pairs, with (0, 0) in
+the top-left corner, x growing to the right and y growing downwards, the same
+way the puzzle inputs are written.
+*/
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+// steps to the left, right, up and down, in that order
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+// the orthogonal steps, followed by up-left, up-right, down-left and down-right
+pub const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, 0), (1, 0), (0, -1), (0, 1),
+    (-1, -1), (1, -1), (-1, 1), (1, 1),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum GridError {
+    Ragged { row: usize, width: usize, expected: usize },
+    InvalidCharacter { x: usize, y: usize, character: char },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Ragged { row, width, expected } =>
+                write!(f, "row {} is {} wide, but the rows above it are {} wide", row, width, expected),
+            GridError::InvalidCharacter { x, y, character } =>
+                write!(f, "unexpected character '{}' at ({}, {})", character, x, y),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> where T: Clone {
+        Grid { width, height, cells: vec![value; width * height] }
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, GridError> {
+        /*
+        Build a grid out of its rows, which all have to be the same width.
+        */
+        let width: usize = rows.first().map_or(0, |row| row.len());
+        let height: usize = rows.len();
+        let mut cells: Vec<T> = Vec::with_capacity(width * height);
+        for (row, each) in rows.into_iter().enumerate() {
+            if each.len() != width {
+                return Err(GridError::Ragged { row, width: each.len(), expected: width });
+            }
+            cells.extend(each);
+        }
+        Ok(Grid { width, height, cells })
+    }
+
+    pub fn parse(lines: &[String], parse_cell: impl Fn(char) -> Option<T>) -> Result<Grid<T>, GridError> {
+        /*
+        Build a grid out of lines of text, one cell per character. Blank lines
+        are skipped, and parse_cell returns None for characters that aren't
+        allowed.
+        */
+        let mut rows: Vec<Vec<T>> = Vec::new();
+        for (y, each_line) in lines.iter().filter(|each| !each.is_empty()).enumerate() {
+            let mut row: Vec<T> = Vec::new();
+            for (x, character) in each_line.chars().enumerate() {
+                row.push(parse_cell(character).ok_or(GridError::InvalidCharacter { x, y, character })?);
+            }
+            rows.push(row);
+        }
+        Grid::from_rows(rows)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, position: (usize, usize)) -> bool {
+        position.0 < self.width && position.1 < self.height
+    }
+
+    pub fn get(&self, position: (usize, usize)) -> Option<&T> {
+        if self.contains(position) {
+            Some(&self.cells[position.1 * self.width + position.0])
+        }else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, position: (usize, usize)) -> Option<&mut T> {
+        if self.contains(position) {
+            Some(&mut self.cells[position.1 * self.width + position.0])
+        }else {
+            None
+        }
+    }
+
+    pub fn step(&self, position: (usize, usize), direction: (isize, isize)) -> Option<(usize, usize)> {
+        /*
+        The position one step away in the given direction, or None if that
+        would be off the grid.
+        */
+        let x: usize = position.0.checked_add_signed(direction.0)?;
+        let y: usize = position.1.checked_add_signed(direction.1)?;
+        if self.contains((x, y)) { Some((x, y)) } else { None }
+    }
+
+    pub fn neighbors4(&self, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        /*
+        The positions to the left, right, above and below, skipping any that
+        are off the grid.
+        */
+        ORTHOGONAL.iter().filter_map(move |direction| self.step(position, *direction))
+    }
+
+    pub fn neighbors8(&self, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        /*
+        The same as neighbors4(), followed by the four diagonal neighbors.
+        */
+        ALL_DIRECTIONS.iter().filter_map(move |direction| self.step(position, *direction))
+    }
+
+    pub fn ray(&self, position: (usize, usize), direction: (isize, isize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        /*
+        Every position from the given one (but not including it) in a straight
+        line to the edge of the grid, nearest first.
+        */
+        let mut current: Option<(usize, usize)> = self.step(position, direction);
+        std::iter::from_fn(move || {
+            let result: Option<(usize, usize)> = current;
+            current = current.and_then(|each| self.step(each, direction));
+            result
+        })
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks() doesn't allow a size of zero, but a zero-width grid has no
+        // cells to split up anyway
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} is outside a grid {} wide", x, self.width);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        /*
+        Every position in the grid, row by row.
+        */
+        let width: usize = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn map<U>(&self, convert: impl Fn(&T) -> U) -> Grid<U> {
+        Grid { width: self.width, height: self.height, cells: self.cells.iter().map(convert).collect() }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: (usize, usize)) -> &T {
+        self.get(position).unwrap_or_else(|| panic!("({}, {}) is outside a {}x{} grid",
+            position.0, position.1, self.width, self.height))
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, position: (usize, usize)) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        self.get_mut(position).unwrap_or_else(|| panic!("({}, {}) is outside a {}x{} grid",
+            position.0, position.1, width, height))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /*
+        Draw the grid one row per line, the same way it would be parsed.
+        */
+        for (y, each_row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for each in each_row {
+                write!(f, "{}", each)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grid<char> {
+        let lines: Vec<String> = vec!["abc".to_string(), "def".to_string()];
+        Grid::parse(&lines, Some).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 1)], 'f');
+        assert_eq!(grid.to_string(), "abc\ndef");
+        let lines: Vec<String> = vec!["12".to_string(), "3x".to_string()];
+        assert_eq!(Grid::parse(&lines, |c| c.to_digit(10)),
+            Err(GridError::InvalidCharacter { x: 1, y: 1, character: 'x' }));
+        assert_eq!(Grid::from_rows(vec![vec![1, 2], vec![3]]),
+            Err(GridError::Ragged { row: 1, width: 1, expected: 2 }));
+        let empty: Grid<char> = Grid::parse(&[], Some).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.to_string(), "");
+    }
+
+    #[test]
+    fn test_get() {
+        let mut grid = example();
+        assert_eq!(grid.get((0, 0)), Some(&'a'));
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get((0, 2)), None);
+        *grid.get_mut((1, 1)).unwrap() = 'E';
+        assert_eq!(grid.row(1), &['d', 'E', 'f']);
+        assert_eq!(grid.column(2).collect::<String>(), "cf");
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = example();
+        assert_eq!(grid.neighbors4((0, 0)).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors4((1, 1)).collect::<Vec<_>>(), vec![(0, 1), (2, 1), (1, 0)]);
+        assert_eq!(grid.neighbors8((1, 1)).collect::<Vec<_>>(), vec![(0, 1), (2, 1), (1, 0), (0, 0), (2, 0)]);
+        assert_eq!(grid.neighbors8((0, 0)).count(), 3);
+    }
+
+    #[test]
+    fn test_ray() {
+        let grid = example();
+        assert_eq!(grid.ray((0, 0), (1, 0)).collect::<Vec<_>>(), vec![(1, 0), (2, 0)]);
+        assert_eq!(grid.ray((2, 1), (-1, -1)).collect::<Vec<_>>(), vec![(1, 0)]);
+        assert_eq!(grid.ray((2, 1), (1, 0)).count(), 0);
+    }
+
+    #[test]
+    fn test_iter_and_map() {
+        let grid = example();
+        assert_eq!(grid.positions().nth(4), Some((1, 1)));
+        let upper: Grid<char> = grid.map(|c| c.to_ascii_uppercase());
+        assert_eq!(upper.to_string(), "ABC\nDEF");
+        let vowels: Vec<(usize, usize)> = grid.iter().filter(|(_, c)| "aeiou".contains(**c)).map(|(p, _)| p).collect();
+        assert_eq!(vowels, vec![(0, 0), (1, 1)]);
+    }
+}
@@ -0,0 +1,310 @@
+/*
+Inclusive integer intervals, and sets of them. These started out as the
+section assignments from Day 4, where "2-4" means sections 2, 3 and 4, but
+they're general enough for any kind of range bookkeeping.
+*/
+use std::fmt;
+use std::cmp::{min, max};
+use std::str::FromStr;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    start: i64,
+    end: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseIntervalError {
+    Malformed(String),
+    Reversed { start: i64, end: i64 },
+}
+
+impl fmt::Display for ParseIntervalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseIntervalError::Malformed(text) =>
+                write!(f, "'{}' is not an interval like 2-4", text),
+            ParseIntervalError::Reversed { start, end } =>
+                write!(f, "the interval {}-{} ends before it starts", start, end),
+        }
+    }
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Interval {
+        /*
+        Create the interval start..=end. Panics if end < start, since an
+        inclusive interval always holds at least one value.
+        */
+        assert!(start <= end, "interval {}-{} ends before it starts", start, end);
+        Interval { start, end }
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+
+    pub fn len(&self) -> Option<u64> {
+        /*
+        The number of values in the interval, or None for the whole of i64,
+        which holds one more value than a u64 can count.
+        */
+        self.start.abs_diff(self.end).checked_add(1)
+    }
+
+    pub fn contains_point(&self, point: i64) -> bool {
+        self.start <= point && point <= self.end
+    }
+
+    pub fn contains(&self, other: &Interval) -> bool {
+        /*
+        Determine if other lies completely inside this interval.
+        */
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        /*
+        Determine if the two intervals share at least one value.
+        */
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn touches(&self, other: &Interval) -> bool {
+        /*
+        Determine if the two intervals overlap or sit right next to each other,
+        like 1-3 and 4-6, so that together they form one unbroken interval.
+        */
+        self.start <= other.end.saturating_add(1) && other.start <= self.end.saturating_add(1)
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        if self.overlaps(other) {
+            Some(Interval::new(max(self.start, other.start), min(self.end, other.end)))
+        }else {
+            None
+        }
+    }
+
+    pub fn union(&self, other: &Interval) -> Option<Interval> {
+        /*
+        Return the single interval covering both, or None if there would be a
+        gap between them. Use an IntervalSet for unions with gaps.
+        */
+        if self.touches(other) {
+            Some(Interval::new(min(self.start, other.start), max(self.end, other.end)))
+        }else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl FromStr for Interval {
+    type Err = ParseIntervalError;
+
+    fn from_str(text: &str) -> Result<Interval, ParseIntervalError> {
+        /*
+        Parse the "start-end" format from Day 4, such as "2-4". A single
+        number, like "6", is the interval 6-6.
+        */
+        let malformed = || ParseIntervalError::Malformed(text.to_string());
+        let trimmed: &str = text.trim();
+        // skip the first character so that a leading minus sign isn't taken
+        // as the separator
+        let (start, end) = match trimmed.char_indices().skip(1).find(|(_, c)| *c == '-') {
+            Some((index, _)) => (&trimmed[..index], &trimmed[index+1..]),
+            None => (trimmed, trimmed),
+        };
+        let start: i64 = i64::from_str(start.trim()).map_err(|_| malformed())?;
+        let end: i64 = i64::from_str(end.trim()).map_err(|_| malformed())?;
+        if end < start {
+            return Err(ParseIntervalError::Reversed { start, end });
+        }
+        Ok(Interval::new(start, end))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IntervalSet {
+    // the start and end of each interval, keyed on the start. No two of them
+    // touch each other.
+    intervals: BTreeMap<i64, i64>,
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet { intervals: BTreeMap::new() }
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        /*
+        Add an interval, merging it with every interval it touches. Those all
+        sit next to each other in the map, ending with the last one starting
+        no later than just past the new interval, so it takes O(k log n) time
+        to merge k of them.
+        */
+        let mut merged: Interval = interval;
+        while let Some((start, end)) = self.intervals.range(..=merged.end.saturating_add(1)).next_back() {
+            let neighbor: Interval = Interval::new(*start, *end);
+            match merged.union(&neighbor) {
+                Some(union) => {
+                    self.intervals.remove(&neighbor.start);
+                    merged = union;
+                },
+                None => break,
+            }
+        }
+        self.intervals.insert(merged.start, merged.end);
+    }
+
+    pub fn intervals(&self) -> impl Iterator<Item = Interval> + '_ {
+        self.intervals.iter().map(|(start, end)| Interval::new(*start, *end))
+    }
+
+    pub fn contains_point(&self, point: i64) -> bool {
+        self.intervals.range(..=point).next_back().is_some_and(|(_, end)| point <= *end)
+    }
+
+    pub fn bounds(&self) -> Option<Interval> {
+        /*
+        The smallest interval covering everything in the set.
+        */
+        match (self.intervals.first_key_value(), self.intervals.last_key_value()) {
+            (Some((start, _)), Some((_, end))) => Some(Interval::new(*start, *end)),
+            _ => None,
+        }
+    }
+
+    pub fn coverage(&self) -> Option<u64> {
+        /*
+        The total number of values covered, counting overlaps only once, or
+        None if that's too many to count in a u64.
+        */
+        self.intervals().try_fold(0u64, |total, each| total.checked_add(each.len()?))
+    }
+
+    pub fn gaps(&self) -> Vec<Interval> {
+        /*
+        The intervals between the first and last covered values that aren't
+        covered by anything.
+        */
+        self.intervals().zip(self.intervals().skip(1))
+            .map(|(before, after)| Interval::new(before.end + 1, after.start - 1))
+            .collect()
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> IntervalSet {
+        let mut set: IntervalSet = IntervalSet::new();
+        for each in iter {
+            set.insert(each);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Interval::from_str("2-4"), Ok(Interval::new(2, 4)));
+        assert_eq!(Interval::from_str("6"), Ok(Interval::new(6, 6)));
+        assert_eq!(Interval::from_str("-5--3"), Ok(Interval::new(-5, -3)));
+        assert_eq!(Interval::from_str("4-2"), Err(ParseIntervalError::Reversed { start: 4, end: 2 }));
+        assert_eq!(Interval::from_str("a-2"), Err(ParseIntervalError::Malformed("a-2".to_string())));
+        assert_eq!(Interval::new(2, 4).to_string(), "2-4");
+    }
+
+    #[test]
+    fn test_contains_and_overlaps() {
+        let outer = Interval::new(2, 8);
+        let inner = Interval::new(3, 7);
+        assert!(outer.contains(&inner));
+        assert!(!inner.contains(&outer));
+        assert!(outer.contains(&outer));
+        assert!(outer.overlaps(&inner));
+        assert!(Interval::new(5, 7).overlaps(&Interval::new(7, 9)));
+        assert!(!Interval::new(2, 4).overlaps(&Interval::new(6, 8)));
+        assert!(!Interval::new(2, 3).overlaps(&Interval::new(4, 5)));
+        assert!(Interval::new(2, 3).touches(&Interval::new(4, 5)));
+    }
+
+    #[test]
+    fn test_intersection_and_union() {
+        let a = Interval::new(2, 6);
+        let b = Interval::new(4, 8);
+        assert_eq!(a.intersection(&b), Some(Interval::new(4, 6)));
+        assert_eq!(a.union(&b), Some(Interval::new(2, 8)));
+        assert_eq!(a.intersection(&Interval::new(7, 9)), None);
+        assert_eq!(a.union(&Interval::new(7, 9)), Some(Interval::new(2, 9)));
+        assert_eq!(a.union(&Interval::new(8, 9)), None);
+        assert_eq!(a.len(), Some(5));
+        assert_eq!(Interval::new(3, 3).len(), Some(1));
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let set: IntervalSet = vec![Interval::new(10, 12), Interval::new(1, 3), Interval::new(2, 5),
+            Interval::new(7, 7), Interval::new(6, 6), Interval::new(20, 20)].into_iter().collect();
+        assert_eq!(set.intervals().collect::<Vec<Interval>>(),
+            vec![Interval::new(1, 7), Interval::new(10, 12), Interval::new(20, 20)]);
+        assert_eq!(set.coverage(), Some(11));
+        assert_eq!(set.gaps(), vec![Interval::new(8, 9), Interval::new(13, 19)]);
+        assert_eq!(set.bounds(), Some(Interval::new(1, 20)));
+        assert!(set.contains_point(11));
+        assert!(!set.contains_point(15));
+        assert_eq!(IntervalSet::new().bounds(), None);
+        assert_eq!(IntervalSet::new().coverage(), Some(0));
+    }
+
+    #[test]
+    fn test_interval_set_bridges_gaps() {
+        let mut set: IntervalSet = IntervalSet::new();
+        set.insert(Interval::new(1, 2));
+        set.insert(Interval::new(5, 6));
+        set.insert(Interval::new(9, 10));
+        set.insert(Interval::new(3, 8));
+        assert_eq!(set.intervals().collect::<Vec<Interval>>(), vec![Interval::new(1, 10)]);
+        assert!(set.gaps().is_empty());
+    }
+
+    #[test]
+    fn test_wide_intervals() {
+        assert_eq!(Interval::new(i64::MIN, i64::MAX).len(), None);
+        assert_eq!(Interval::new(i64::MIN + 1, i64::MAX).len(), Some(u64::MAX));
+        assert_eq!(Interval::new(-5, i64::MAX).len(), Some(i64::MAX as u64 + 6));
+        let set: IntervalSet = vec![Interval::new(i64::MIN, -1), Interval::new(1, i64::MAX)].into_iter().collect();
+        assert_eq!(set.coverage(), Some(u64::MAX));
+        assert_eq!(set.gaps(), vec![Interval::new(0, 0)]);
+        let set: IntervalSet = vec![Interval::new(i64::MIN, 0), Interval::new(1, i64::MAX)].into_iter().collect();
+        assert_eq!(set.coverage(), None);
+        assert_eq!(set.bounds(), Some(Interval::new(i64::MIN, i64::MAX)));
+    }
+
+    #[test]
+    fn test_interval_set_insert_order() {
+        // the same intervals in any order make the same set
+        let intervals: Vec<Interval> = (0..40).map(|each| Interval::new(each * 7 % 40 * 3, each * 7 % 40 * 3 + each % 4)).collect();
+        let forwards: IntervalSet = intervals.iter().copied().collect();
+        let backwards: IntervalSet = intervals.iter().rev().copied().collect();
+        assert_eq!(forwards, backwards);
+        for point in -2..125 {
+            let expected: bool = intervals.iter().any(|each| each.contains_point(point));
+            assert_eq!(forwards.contains_point(point), expected, "{}", point);
+        }
+    }
+}
@@ -1,33 +1,64 @@
 //mod day01;
 //mod day02;
-//mod day02_alt_solution;
-//mod day03;
-//mod day04;
-//mod day05;
-//mod day06;
-//mod day07;
-//mod day08;
-//mod day09;
-//mod day10;
+mod day02_alt_solution;
+mod day02_simulator;
+mod day03;
+mod day04;
+mod day05;
+mod day06;
+mod day07;
+mod day08;
+mod day09;
+mod day10;
 //mod day11;
-//mod day12;
+mod day12;
 //mod day13;
 mod day14;
+mod grid;
+mod interval;
+
+use std::env;
 
 fn main() {
-    //day01::main();
-    //day02::main();
-    //day02_alt_solution::main();
-    //day03::main();
-    //day04::main();
-    //day05::main();
-    //day06::main();
-    //day07::main();
-    //day08::main();
-    //day09::main();
-    //day10::main();
-    //day11::main();
-    //day12::main();
-    //day13::main();
-    day14::main();
+    // with no arguments, run the puzzles being worked on. Otherwise run the
+    // days named on the command line, e.g. `cargo run -- 7 9`
+    let days: Vec<String> = env::args().skip(1).collect();
+    if days.is_empty() {
+        //day01::main();
+        //day02::main();
+        //day02_alt_solution::main();
+        day02_simulator::main();
+        //day03::main();
+        //day04::main();
+        //day05::main();
+        //day06::main();
+        //day07::main();
+        //day08::main();
+        //day09::main();
+        //day10::main();
+        //day11::main();
+        //day12::main();
+        //day13::main();
+        day14::main();
+        return;
+    }
+    for each_day in days {
+        match each_day.as_str() {
+            "2" => {
+                day02_alt_solution::main();
+                day02_simulator::main();
+            },
+            "3" => day03::main(),
+            "4" => day04::main(),
+            "5" => day05::main(),
+            "6" => day06::main(),
+            "7" => day07::main(),
+            "8" => day08::main(),
+            "9" => day09::main(),
+            "10" => day10::main(),
+            "12" => day12::main(),
+            "14" => day14::main(),
+            _ => eprintln!("There's no solution for day {} to run", each_day),
+        }
+    }
 }